@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag telling workers to stop. A child token is cancelled together with its parent
+/// but cancelling the child leaves the parent untouched.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    parent: Option<Arc<CancellationToken>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn child(&self) -> Self {
+        CancellationToken {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            parent: Some(Arc::new(self.clone())),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || self.parent.as_ref().is_some_and(|parent| parent.is_cancelled())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_cancellation() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+
+        clone.cancel();
+
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn child_follows_parent_but_not_the_other_way() {
+        let parent = CancellationToken::new();
+        let first = parent.child();
+        let second = parent.child();
+
+        first.cancel();
+        assert!(!parent.is_cancelled());
+        assert!(!second.is_cancelled());
+
+        parent.cancel();
+        assert!(second.is_cancelled());
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::combination_finder::{Combination, Position};
+use crate::hasher::HashAlgorithm;
+use crate::password::Password;
+use crate::renderer::Rendering;
+
+/// Word length pattern a finder was searching when the checkpoint was written.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InFlight {
+    pub pattern: Vec<usize>,
+    pub position: Position,
+}
+
+/// Target found before the checkpoint was written, `target` is its index in the target list.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FoundTarget {
+    pub target: usize,
+    pub digest: String,
+    pub phrase: String,
+    #[serde(default)]
+    pub words: Vec<String>,
+    pub algorithm: HashAlgorithm,
+    #[serde(default)]
+    pub rendering: Option<Rendering>,
+}
+
+/// Progress of a solve run, enough to continue it without hashing finished work again.
+/// `fingerprint` identifies the phrase, dictionary, targets and rules it belongs to.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub fingerprint: String,
+    pub completed: Vec<Vec<usize>>,
+    pub in_flight: Vec<InFlight>,
+    pub found: Vec<FoundTarget>,
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Writes a sibling file first and renames it, so a crash never leaves a truncated checkpoint.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&partial, path)
+    }
+
+    /// Marks the passwords found by the checkpointed run.
+    pub fn restore(&self, passwords: &mut [Password]) {
+        for found in &self.found {
+            if let Some(password) = passwords.get_mut(found.target).filter(|password| password.digest() == found.digest) {
+                password.mark_found(&found.phrase, found.words.clone(), found.algorithm, found.rendering.clone(), None);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PatternProgress {
+    done: bool,
+    searched: bool,
+    pending: BTreeSet<Vec<usize>>,
+    last: Option<Position>,
+}
+
+/// Tracks which combinations of every word length pattern were sent and hashed, so
+/// a checkpoint only skips combinations whose hashes were compared with the targets.
+#[derive(Debug)]
+pub struct Progress {
+    patterns: Vec<Vec<usize>>,
+    state: Mutex<Vec<PatternProgress>>,
+}
+
+impl Progress {
+    pub fn new(patterns: Vec<Vec<usize>>, checkpoint: Option<&Checkpoint>) -> Self {
+        let state = patterns.iter()
+            .map(|pattern| PatternProgress {
+                done: checkpoint.is_some_and(|checkpoint| checkpoint.completed.contains(pattern)),
+                last: checkpoint
+                    .and_then(|checkpoint| checkpoint.in_flight.iter().find(|in_flight| in_flight.pattern == *pattern))
+                    .map(|in_flight| in_flight.position.clone()),
+                ..PatternProgress::default()
+            })
+            .collect();
+        Progress {
+            patterns,
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn is_done(&self, pattern: usize) -> bool {
+        self.state.lock().unwrap()[pattern].done
+    }
+
+    /// Position to resume an interrupted pattern from.
+    pub fn position(&self, pattern: usize) -> Option<Position> {
+        self.state.lock().unwrap()[pattern].last.clone()
+    }
+
+    pub fn emitted(&self, combination: &Combination) {
+        let mut state = self.state.lock().unwrap();
+        let progress = &mut state[combination.pattern];
+        progress.pending.insert(combination.position.clone());
+        progress.last = Some(Position::After(combination.position.clone()));
+    }
+
+    pub fn hashed(&self, combination: &Combination) {
+        let mut state = self.state.lock().unwrap();
+        let progress = &mut state[combination.pattern];
+        progress.pending.remove(&combination.position);
+        progress.done = progress.searched && progress.pending.is_empty();
+    }
+
+    /// Records that the finder of `pattern` searched it to the end.
+    pub fn searched(&self, pattern: usize) {
+        let mut state = self.state.lock().unwrap();
+        let progress = &mut state[pattern];
+        progress.searched = true;
+        progress.done = progress.pending.is_empty();
+    }
+
+    pub fn checkpoint(&self, fingerprint: &str, passwords: &[Password]) -> Checkpoint {
+        let state = self.state.lock().unwrap();
+        let completed = self.patterns.iter().zip(state.iter())
+            .filter(|(_, progress)| progress.done)
+            .map(|(pattern, _)| pattern.clone())
+            .collect();
+        let in_flight = self.patterns.iter().zip(state.iter())
+            .filter(|(_, progress)| !progress.done)
+            .filter_map(|(pattern, progress)| {
+                let position = match progress.pending.first() {
+                    Some(position) => Some(Position::At(position.clone())),
+                    None => progress.last.clone(),
+                };
+                position.map(|position| InFlight { pattern: pattern.clone(), position })
+            })
+            .collect();
+        let found = passwords.iter().enumerate()
+            .filter_map(|(target, password)| Some(FoundTarget {
+                target,
+                digest: password.digest().to_string(),
+                phrase: password.phrase()?.to_string(),
+                words: password.words().to_vec(),
+                algorithm: password.algorithm()?,
+                rendering: password.rendering().cloned(),
+            }))
+            .collect();
+        Checkpoint {
+            fingerprint: fingerprint.to_string(),
+            completed,
+            in_flight,
+            found,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combination(pattern: usize, position: Vec<usize>) -> Combination {
+        Combination { pattern, rank: 0, position, classes: vec![] }
+    }
+
+    fn progress() -> Progress {
+        Progress::new(vec![vec![4, 4], vec![3, 5], vec![5, 3]], None)
+    }
+
+    #[test]
+    fn checkpoint_resumes_at_oldest_combination_not_hashed() {
+        let progress = progress();
+        progress.emitted(&combination(0, vec![0, 1]));
+        progress.emitted(&combination(0, vec![0, 3]));
+        progress.emitted(&combination(0, vec![2, 2]));
+        progress.hashed(&combination(0, vec![0, 3]));
+
+        let checkpoint = progress.checkpoint("config", &[]);
+
+        assert_eq!(checkpoint.in_flight, vec![InFlight { pattern: vec![4, 4], position: Position::At(vec![0, 1]) }]);
+        assert!(checkpoint.completed.is_empty());
+    }
+
+    #[test]
+    fn checkpoint_resumes_after_last_combination_once_all_are_hashed() {
+        let progress = progress();
+        progress.emitted(&combination(1, vec![0, 1]));
+        progress.emitted(&combination(1, vec![1, 0]));
+        progress.hashed(&combination(1, vec![1, 0]));
+        progress.hashed(&combination(1, vec![0, 1]));
+
+        let checkpoint = progress.checkpoint("config", &[]);
+
+        assert_eq!(checkpoint.in_flight, vec![InFlight { pattern: vec![3, 5], position: Position::After(vec![1, 0]) }]);
+    }
+
+    #[test]
+    fn pattern_completes_once_searched_and_hashed() {
+        let progress = progress();
+        progress.emitted(&combination(2, vec![0, 0]));
+        progress.searched(2);
+        assert!(!progress.is_done(2));
+
+        progress.hashed(&combination(2, vec![0, 0]));
+        progress.searched(1);
+
+        assert_eq!(progress.checkpoint("config", &[]).completed, vec![vec![3, 5], vec![5, 3]]);
+    }
+
+    #[test]
+    fn new_continues_from_checkpoint() {
+        let checkpoint = Checkpoint {
+            fingerprint: String::from("config"),
+            completed: vec![vec![5, 3]],
+            in_flight: vec![InFlight { pattern: vec![4, 4], position: Position::At(vec![1, 2]) }],
+            found: vec![],
+        };
+
+        let progress = Progress::new(vec![vec![4, 4], vec![3, 5], vec![5, 3]], Some(&checkpoint));
+
+        assert_eq!(progress.position(0), Some(Position::At(vec![1, 2])));
+        assert_eq!(progress.position(1), None);
+        assert!(progress.is_done(2));
+        assert_eq!(progress.checkpoint("config", &[]), checkpoint);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut password = Password::new(HashAlgorithm::Md5, md5::compute(b"this is test").to_vec());
+        password.mark_found("this is test", vec![], HashAlgorithm::Md5, Some(Rendering::default()), None);
+        let checkpoint = progress().checkpoint("config", &[password]);
+        let path = std::env::temp_dir().join(format!("anagram-checkpoint-{}.json", std::process::id()));
+
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded.unwrap(), checkpoint);
+        assert_eq!(checkpoint.found[0].phrase, "this is test");
+    }
+
+    #[test]
+    fn restore_marks_found_passwords() {
+        let mut found = Password::new(HashAlgorithm::Md5, md5::compute(b"this is test").to_vec());
+        found.mark_found("this is test", vec![], HashAlgorithm::Md5, Some(Rendering::default()), None);
+        let checkpoint = progress().checkpoint("config", &[found]);
+        let mut passwords = vec![Password::new(HashAlgorithm::Md5, md5::compute(b"this is test").to_vec())];
+
+        checkpoint.restore(&mut passwords);
+
+        assert_eq!(passwords[0].phrase(), Some("this is test"));
+    }
+}
@@ -0,0 +1,110 @@
+use unicode_normalization::char::is_combining_mark;
+
+const APOSTROPHES: [char; 2] = ['\'', '\u{2019}'];
+const HYPHENS: [char; 2] = ['-', '\u{2010}'];
+
+/// Steps turning a wordlist line into a dictionary word, applied before the word is validated.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CleanRules {
+    /// Keeps entries containing digits with the digits removed, instead of dropping them.
+    pub keep_digits: bool,
+    /// Trims everything but letters from both ends, e.g. `"test!"` becomes `test`.
+    pub trim: bool,
+    /// Strips a trailing possessive `'s`, e.g. `abacus's` becomes `abacus`.
+    pub strip_possessive: bool,
+    /// Removes hyphens and apostrophes inside words, e.g. `o'clock` becomes `oclock`.
+    pub join: bool,
+}
+
+impl Default for CleanRules {
+    fn default() -> Self {
+        CleanRules {
+            keep_digits: false,
+            trim: true,
+            strip_possessive: true,
+            join: false,
+        }
+    }
+}
+
+impl CleanRules {
+    /// Cleaned word of a wordlist `line`, `None` when the entry is dropped.
+    pub fn clean(&self, line: &str) -> Option<String> {
+        let mut word = line.trim().to_string();
+        if word.chars().any(|c| c.is_numeric()) {
+            if !self.keep_digits {
+                return None;
+            }
+            word.retain(|c| !c.is_numeric());
+        }
+        if self.trim {
+            word = word.trim_matches(|c| !is_letter(c)).to_string();
+        }
+        if self.strip_possessive {
+            word = strip_possessive(&word).to_string();
+        }
+        if self.join {
+            word.retain(|c| !APOSTROPHES.contains(&c) && !HYPHENS.contains(&c));
+        }
+        if word.is_empty() { None } else { Some(word) }
+    }
+}
+
+fn is_letter(c: char) -> bool {
+    c.is_alphabetic() || is_combining_mark(c)
+}
+
+fn strip_possessive(word: &str) -> &str {
+    let stripped = word.strip_suffix(['s', 'S']).and_then(|rest| rest.strip_suffix(APOSTROPHES));
+    match stripped {
+        Some(stem) if !stem.is_empty() => stem,
+        _ => word,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("word!", Some("word"); "trailing punctuation")]
+    #[test_case("\"quoted\"", Some("quoted"); "both ends")]
+    #[test_case("mors\n", Some("mors"); "trailing newline")]
+    #[test_case("cafe\u{301}.", Some("cafe\u{301}"); "trailing combining accent")]
+    #[test_case("a's", Some("a"); "possessive of single letter")]
+    #[test_case("abacus's", Some("abacus"); "possessive")]
+    #[test_case("apliqué's", Some("apliqué"); "possessive with accent")]
+    #[test_case("JONES\u{2019}S", Some("JONES"); "typographic apostrophe")]
+    #[test_case("Jones'", Some("Jones"); "plural possessive")]
+    #[test_case("o'clock", Some("o'clock"); "internal apostrophe kept")]
+    #[test_case("work34", None; "digits")]
+    #[test_case("?!", None; "no letters")]
+    fn clean_with_default_rules(line: &str, expected: Option<&str>) {
+        assert_eq!(CleanRules::default().clean(line).as_deref(), expected);
+    }
+
+    #[test_case("o'clock", Some("oclock"); "internal apostrophe")]
+    #[test_case("well-known", Some("wellknown"); "internal hyphen")]
+    #[test_case("rock'n'roll's", Some("rocknroll"); "possessive stripped before joining")]
+    fn clean_joins_words(line: &str, expected: Option<&str>) {
+        let rules = CleanRules { join: true, ..CleanRules::default() };
+
+        assert_eq!(rules.clean(line).as_deref(), expected);
+    }
+
+    #[test_case("work34", Some("work"); "trailing digits")]
+    #[test_case("conta1n", Some("contan"); "internal digit")]
+    #[test_case("42", None; "only digits")]
+    fn clean_keeps_entries_with_digits(line: &str, expected: Option<&str>) {
+        let rules = CleanRules { keep_digits: true, ..CleanRules::default() };
+
+        assert_eq!(rules.clean(line).as_deref(), expected);
+    }
+
+    #[test]
+    fn clean_without_rules_only_trims_whitespace() {
+        let rules = CleanRules { keep_digits: true, trim: false, strip_possessive: false, join: false };
+
+        assert_eq!(rules.clean(" it's! ").as_deref(), Some("it's!"));
+    }
+}
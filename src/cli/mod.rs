@@ -0,0 +1,504 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand};
+
+use anagram_solver_rust::target_list::read_targets;
+use anagram_solver_rust::{Casing, CleanRules, DictionarySource, HashAlgorithm, HashTemplate, Password, ProgressMode, RenderRules, SolverBuilder, TargetFormat, TargetSpec};
+
+#[derive(Parser, Debug)]
+#[command(name = "anagram-solver-rust", version, about = "Finds multi-word anagrams of a phrase matching target digests")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Search for anagrams of the phrase whose digest matches one of the targets
+    Solve(Box<SolveArgs>),
+    /// List multi-word anagrams of the phrase without any hash targets
+    Anagrams(AnagramsArgs),
+    /// Print the phrases a potfile knows for the targets without searching
+    Show(ShowArgs),
+}
+
+/// How results are written to stdout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One line per target digest.
+    Text,
+    /// A single summary object once the run stops.
+    Json,
+    /// One event object per line while the run goes on.
+    Jsonl,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 3] = [OutputFormat::Text, OutputFormat::Json, OutputFormat::Jsonl];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        OutputFormat::ALL.iter()
+            .find(|format| format.name() == name)
+            .copied()
+            .ok_or_else(|| format!("unknown output format '{}', expected one of: {}", s, OutputFormat::ALL.map(|f| f.name()).join(", ")))
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct SearchArgs {
+    /// Phrase whose letters make up the anagram pool, spaces, punctuation and case are ignored
+    #[arg(short, long)]
+    pub phrase: String,
+
+    /// Word list file or directory used to build anagram candidates, may be repeated to merge several.
+    /// TAG names the list in results, lists of a lower RANK are searched first (core@0=wordlist jargon@1=jargon.txt)
+    #[arg(short, long = "dictionary", value_name = "[TAG[@RANK]=]PATH", default_values = ["wordlist"], value_parser = str::parse::<DictionarySource>)]
+    pub dictionaries: Vec<DictionarySource>,
+
+    /// Shortest word allowed in a candidate phrase
+    #[arg(long, default_value_t = 2)]
+    pub min_word_len: usize,
+
+    /// Longest word allowed in a candidate phrase
+    #[arg(long, default_value_t = 10)]
+    pub max_word_len: usize,
+
+    /// Maximum number of words in a candidate phrase
+    #[arg(short = 'w', long, default_value_t = 4)]
+    pub max_words: usize,
+
+    /// Total number of letters in a candidate phrase, must match the phrase letter count
+    #[arg(short, long)]
+    pub target_length: Option<usize>,
+
+    /// Match accented letters with their base letter, words are still written with their accents
+    #[arg(long)]
+    pub strip_accents: bool,
+
+    /// Do not fold possessives (john's -> john), entries ending in 's are then dropped unless --join-words is set
+    #[arg(long)]
+    pub no_strip_possessives: bool,
+
+    /// Join dictionary entries with inner hyphens or apostrophes into one word (o'clock -> oclock)
+    #[arg(long)]
+    pub join_words: bool,
+
+    /// Keep dictionary entries containing digits, with the digits removed
+    #[arg(long)]
+    pub keep_digits: bool,
+
+    /// Number of combination finder workers running at the same time
+    #[arg(short = 'j', long, default_value_t = 6)]
+    pub max_workers: usize,
+}
+
+#[derive(Args, Debug)]
+pub struct TargetArgs {
+    /// Target digest in hex or base64, optionally prefixed with its algorithm as name:digest or $name$digest
+    /// (sha256:9f86... or $sha256$9f86...), may be repeated. The name is one of the --algorithm values;
+    /// crypt(3) strings such as $1$, $5$, $6$ or $2b$ hashes are not supported
+    #[arg(short = 'D', long = "digest", value_name = "DIGEST")]
+    pub digests: Vec<String>,
+
+    /// File with target digests, one per line in --target-format, `-` reads stdin
+    #[arg(short = 'f', long, value_name = "PATH")]
+    pub digests_file: Option<PathBuf>,
+
+    /// Layout of --digest values and digest file lines (plain, user-hash, hash-salt)
+    #[arg(long, value_name = "FORMAT", default_value = "plain", value_parser = str::parse::<TargetFormat>)]
+    pub target_format: TargetFormat,
+
+    /// Algorithm of target digests without an algorithm prefix, detected from the digest format by default
+    #[arg(short, long, value_parser = str::parse::<HashAlgorithm>)]
+    pub algorithm: Option<HashAlgorithm>,
+}
+
+#[derive(Args, Debug)]
+pub struct SolveArgs {
+    #[command(flatten)]
+    pub search: SearchArgs,
+
+    #[command(flatten)]
+    pub targets: TargetArgs,
+
+    /// Skip targets cracked by earlier runs and append the ones found to this file
+    #[arg(long, value_name = "PATH")]
+    pub potfile: Option<PathBuf>,
+
+    /// Text prepended to every candidate phrase before hashing, e.g. a salt
+    #[arg(long, value_name = "TEXT", default_value = "")]
+    pub prefix: String,
+
+    /// Text appended to every candidate phrase before hashing, e.g. a pepper, digest file salts follow it
+    #[arg(long, value_name = "TEXT", default_value = "")]
+    pub suffix: String,
+
+    /// Hash candidates as HMAC with this key
+    #[arg(long, value_name = "KEY")]
+    pub hmac_key: Option<String>,
+
+    /// Number of hashing rounds, every round after the first hashes the previous hex digest
+    #[arg(long, default_value_t = 1)]
+    pub iterations: usize,
+
+    /// Separator placed between words of a candidate phrase, may be repeated
+    #[arg(long = "separator", value_name = "TEXT", default_values = [" "])]
+    pub separators: Vec<String>,
+
+    /// Casing applied to a candidate phrase (lower, upper, title, camel), may be repeated
+    #[arg(long = "case", value_name = "CASE", default_values = ["lower"], value_parser = str::parse::<Casing>)]
+    pub casings: Vec<Casing>,
+
+    /// Text appended to a candidate phrase, e.g. punctuation, may be repeated
+    #[arg(long = "trailing", value_name = "TEXT", default_values = [""])]
+    pub trailing: Vec<String>,
+
+    /// Number of workers hashing candidate phrases, independent of --max-workers
+    #[arg(long, default_value_t = 2)]
+    pub hash_workers: usize,
+
+    /// Write search progress and found passwords to this file periodically and on exit
+    #[arg(long, value_name = "PATH")]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Seconds between checkpoints
+    #[arg(long, value_name = "SECS", default_value_t = 60)]
+    pub checkpoint_interval: u64,
+
+    /// Continue from the --checkpoint file without testing finished work again
+    #[arg(long, requires = "checkpoint")]
+    pub resume: bool,
+
+    /// How to show progress (auto, bar, lines, off), auto draws a bar when stderr is a terminal
+    #[arg(long, value_name = "MODE", default_value = "auto", value_parser = str::parse::<ProgressMode>)]
+    pub progress: ProgressMode,
+
+    /// Seconds between progress lines with --progress lines
+    #[arg(long, value_name = "SECS", default_value_t = 10)]
+    pub progress_interval: u64,
+
+    /// Format of the results on stdout (text, json, jsonl)
+    #[arg(long, value_name = "FORMAT", default_value = "text", value_parser = str::parse::<OutputFormat>)]
+    pub format: OutputFormat,
+
+    /// Report the size of the search space per word length pattern instead of searching it
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Hash a sample phrase for this many seconds to estimate how long the search takes
+    #[arg(long, value_name = "SECS", requires = "dry_run")]
+    pub calibrate: Option<f64>,
+}
+
+#[derive(Args, Debug)]
+pub struct ShowArgs {
+    #[command(flatten)]
+    pub targets: TargetArgs,
+
+    /// Potfile written by earlier solve runs
+    #[arg(long, value_name = "PATH")]
+    pub potfile: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct AnagramsArgs {
+    #[command(flatten)]
+    pub search: SearchArgs,
+
+    /// Also list every distinct word order of each anagram
+    #[arg(long)]
+    pub permutations: bool,
+
+    /// Write anagrams to this file instead of stdout
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Stop after this many anagrams
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+
+    /// Follow every word with the tags of the dictionaries listing it, e.g. stout[core,jargon]
+    #[arg(long)]
+    pub tags: bool,
+}
+
+impl SearchArgs {
+    pub fn builder(&self) -> SolverBuilder {
+        let mut builder = SolverBuilder::new()
+            .dictionaries(self.dictionaries.clone())
+            .phrase(&self.phrase)
+            .min_word_len(self.min_word_len)
+            .max_word_len(self.max_word_len)
+            .max_words(self.max_words)
+            .max_workers(self.max_workers)
+            .strip_accents(self.strip_accents)
+            .clean_rules(self.clean_rules());
+        if let Some(target_length) = self.target_length {
+            builder = builder.target_length(target_length);
+        }
+        builder
+    }
+
+    pub fn clean_rules(&self) -> CleanRules {
+        CleanRules {
+            keep_digits: self.keep_digits,
+            strip_possessive: !self.no_strip_possessives,
+            join: self.join_words,
+            ..CleanRules::default()
+        }
+    }
+}
+
+impl TargetArgs {
+    pub fn load_targets(&self) -> Result<Vec<TargetSpec>, String> {
+        let mut targets = vec![];
+        for digest in &self.digests {
+            targets.extend(TargetSpec::parse(digest, self.target_format)?);
+        }
+        if let Some(path) = &self.digests_file {
+            let loaded = if path == Path::new("-") {
+                read_targets(io::stdin().lock(), self.target_format)
+            } else {
+                File::open(path).map_err(|e| e.to_string()).and_then(|file| read_targets(BufReader::new(file), self.target_format))
+            };
+            targets.extend(loaded.map_err(|e| format!("cannot read digests file {}: {}", path.display(), e))?);
+        }
+        Ok(targets)
+    }
+
+    /// Targets without salts applied, enough to look their digests up.
+    pub fn passwords(&self) -> Result<Vec<Password>, String> {
+        self.load_targets()?.into_iter()
+            .map(|spec| {
+                let password = Password::parse(&spec.digest, self.algorithm)?;
+                Ok(match &spec.label {
+                    Some(label) => password.with_label(label),
+                    None => password,
+                })
+            })
+            .collect()
+    }
+}
+
+impl SolveArgs {
+    pub fn template(&self) -> HashTemplate {
+        HashTemplate {
+            prefix: self.prefix.clone(),
+            suffix: self.suffix.clone(),
+            hmac_key: self.hmac_key.as_ref().map(|key| key.as_bytes().to_vec()),
+            iterations: self.iterations,
+        }
+    }
+
+    pub fn render_rules(&self) -> RenderRules {
+        RenderRules {
+            separators: self.separators.clone(),
+            casings: self.casings.clone(),
+            trailing: self.trailing.clone(),
+        }
+    }
+
+    pub fn builder(&self) -> Result<SolverBuilder, String> {
+        let mut builder = self.search.builder()
+            .template(self.template())
+            .render_rules(self.render_rules())
+            .hash_workers(self.hash_workers)
+            .checkpoint_interval(Duration::from_secs(self.checkpoint_interval))
+            .resume(self.resume)
+            .target_specs(self.targets.load_targets()?);
+        if let Some(path) = &self.checkpoint {
+            builder = builder.checkpoint(path);
+        }
+        if let Some(path) = &self.potfile {
+            builder = builder.potfile(path);
+        }
+        if let Some(algorithm) = self.targets.algorithm {
+            builder = builder.algorithm(algorithm);
+        }
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn parse(args: &[&str]) -> SolveArgs {
+        let mut argv = vec!["anagram-solver-rust", "solve"];
+        argv.extend_from_slice(args);
+        match Cli::try_parse_from(argv).unwrap().command {
+            Command::Solve(args) => *args,
+            command => panic!("unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn solve_uses_defaults_for_omitted_flags() {
+        let args = parse(&["--phrase", "poultry outwits ants", "--digest", "e4820b45d2277f3844eac66c903e84be"]);
+
+        assert_eq!(args.search.dictionaries, vec![DictionarySource::new("wordlist")]);
+        assert_eq!((args.search.min_word_len, args.search.max_word_len, args.search.max_words, args.search.max_workers), (2, 10, 4, 6));
+        assert_eq!(args.search.target_length, None);
+        assert_eq!(args.render_rules(), RenderRules::default());
+        assert!(args.builder().unwrap().build().is_ok());
+    }
+
+    #[test]
+    fn solve_collects_repeated_digests() {
+        let args = parse(&["-p", "sitt thesis", "-D", "8c6d115258631625b625486f81b09532", "-D", "d1e744eced74494c7a5292a93252eb6c"]);
+
+        let digests = args.targets.load_targets().unwrap().into_iter().map(|spec| spec.digest).collect::<Vec<String>>();
+        assert_eq!(digests, vec!["8c6d115258631625b625486f81b09532", "d1e744eced74494c7a5292a93252eb6c"]);
+    }
+
+    #[test]
+    fn load_targets_parses_digests_in_target_format() {
+        let args = parse(&["-p", "sitt thesis", "--target-format", "user-hash", "-D", "alice:8c6d115258631625b625486f81b09532"]);
+
+        assert_eq!(args.targets.load_targets().unwrap(), vec![TargetSpec {
+            digest: String::from("8c6d115258631625b625486f81b09532"),
+            label: Some(String::from("alice")),
+            salt: None,
+        }]);
+        assert!(parse(&["-p", "sitt thesis", "--target-format", "user-hash", "-D", "8c6d115258631625b625486f81b09532"]).targets.load_targets().is_err());
+    }
+
+    #[test_case(&["--min-word-len", "6", "--max-word-len", "5"]; "min above max")]
+    #[test_case(&["--max-workers", "0"]; "zero workers")]
+    #[test_case(&["--hash-workers", "0"]; "zero hash workers")]
+    #[test_case(&["--target-length", "11"]; "target length inconsistent with phrase")]
+    #[test_case(&["-D", "not-a-digest"]; "malformed digest")]
+    #[test_case(&["--iterations", "0"]; "zero hash iterations")]
+    #[test_case(&["--checkpoint", "run.json", "--checkpoint-interval", "0"]; "zero checkpoint interval")]
+    fn builder_rejects_conflicting_values(extra: &[&str]) {
+        let mut argv = vec!["-p", "sitt thesis", "-D", "8c6d115258631625b625486f81b09532"];
+        argv.extend_from_slice(extra);
+
+        assert!(parse(&argv).builder().unwrap().build().is_err());
+    }
+
+    #[test]
+    fn solve_maps_cleaning_flags() {
+        let args = parse(&["-p", "sitt thesis", "--no-strip-possessives", "--join-words"]);
+
+        assert_eq!(args.search.clean_rules(), CleanRules {
+            keep_digits: false,
+            trim: true,
+            strip_possessive: false,
+            join: true,
+        });
+    }
+
+    #[test]
+    fn solve_collects_repeated_render_rules() {
+        let args = parse(&["-p", "sitt thesis", "--separator", "", "--separator", "-", "--case", "title", "--case", "upper", "--trailing", "!"]);
+
+        assert_eq!(args.render_rules(), RenderRules {
+            separators: vec![String::new(), String::from("-")],
+            casings: vec![Casing::Title, Casing::Upper],
+            trailing: vec![String::from("!")],
+        });
+    }
+
+    #[test]
+    fn resume_requires_checkpoint() {
+        let argv = ["anagram-solver-rust", "solve", "-p", "sitt thesis", "-D", "8c6d115258631625b625486f81b09532", "--resume"];
+
+        assert!(Cli::try_parse_from(argv).is_err());
+        assert!(parse(&["-p", "sitt thesis", "-D", "8c6d115258631625b625486f81b09532", "--checkpoint", "run.json", "--resume"]).resume);
+    }
+
+    #[test]
+    fn solve_parses_progress_mode() {
+        let args = parse(&["-p", "sitt thesis", "--progress", "lines", "--progress-interval", "30"]);
+
+        assert_eq!((args.progress, args.progress_interval), (ProgressMode::Lines, 30));
+        assert_eq!(args.format, OutputFormat::Text);
+        assert_eq!(parse(&["-p", "sitt thesis"]).progress, ProgressMode::Auto);
+        assert!(Cli::try_parse_from(["anagram-solver-rust", "solve", "-p", "sitt thesis", "--progress", "fancy"]).is_err());
+    }
+
+    #[test_case("text", OutputFormat::Text)]
+    #[test_case("JSON", OutputFormat::Json)]
+    #[test_case("jsonl", OutputFormat::Jsonl)]
+    fn solve_parses_output_format(name: &str, expected: OutputFormat) {
+        assert_eq!(parse(&["-p", "sitt thesis", "--format", name]).format, expected);
+    }
+
+    #[test]
+    fn calibrate_requires_dry_run() {
+        let argv = ["anagram-solver-rust", "solve", "-p", "sitt thesis", "--calibrate", "2"];
+
+        assert!(Cli::try_parse_from(argv).is_err());
+        assert_eq!(parse(&["-p", "sitt thesis", "--dry-run", "--calibrate", "0.5"]).calibrate, Some(0.5));
+    }
+
+    #[test]
+    fn solve_collects_repeated_dictionaries() {
+        let args = parse(&["-p", "sitt thesis", "-d", "wordlist", "-d", "jargon@1=lists/jargon.txt"]);
+
+        assert_eq!(args.search.dictionaries, vec![
+            DictionarySource::new("wordlist"),
+            DictionarySource::new("lists/jargon.txt").with_tag("jargon").with_rank(1),
+        ]);
+    }
+
+    #[test]
+    fn anagrams_accepts_search_and_output_flags() {
+        let cli = Cli::try_parse_from(["anagram-solver-rust", "anagrams", "-p", "sitt thesis", "-d", "testlist", "--permutations", "-n", "5", "-o", "out.txt"]).unwrap();
+
+        match cli.command {
+            Command::Anagrams(args) => {
+                assert_eq!(args.search.dictionaries, vec![DictionarySource::new("testlist")]);
+                assert!(args.permutations);
+                assert_eq!(args.limit, Some(5));
+                assert_eq!(args.output, Some(PathBuf::from("out.txt")));
+                assert!(args.search.builder().build().is_ok());
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn show_requires_potfile() {
+        assert!(Cli::try_parse_from(["anagram-solver-rust", "show", "-D", "8c6d115258631625b625486f81b09532"]).is_err());
+
+        let cli = Cli::try_parse_from(["anagram-solver-rust", "show", "--potfile", "run.pot", "--target-format", "user-hash", "-D", "alice:8c6d115258631625b625486f81b09532"]).unwrap();
+        match cli.command {
+            Command::Show(args) => assert_eq!(args.targets.passwords().unwrap()[0].labels(), ["alice"]),
+            command => panic!("unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn load_digests_reports_missing_file() {
+        let args = parse(&["-p", "sitt thesis", "-f", "missing-digests"]);
+
+        assert!(args.targets.load_targets().is_err());
+    }
+}
@@ -1,183 +1,573 @@
-use std::{sync::mpsc::Sender, fmt::Error};
+use std::cell::Cell;
+use std::sync::mpsc::SyncSender;
+use std::sync::Arc;
 
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
-struct DictionaryIterator {
-    max_values: Vec<isize>,
-    state: Vec<isize>
+use crate::cancellation::CancellationToken;
+use crate::checkpoint::Progress;
+use crate::dictionary::{AnagramClass, WordRanks};
+use crate::normalizer::Normalizer;
+use crate::progress::Meter;
+use crate::signature::{Alphabet, Signature};
+
+/// Anagram classes filling every slot of a word length pattern, with the search tier and
+/// the position the finder emitted them at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Combination {
+    pub pattern: usize,
+    pub rank: usize,
+    pub position: Vec<usize>,
+    pub classes: Vec<AnagramClass>,
 }
 
-impl DictionaryIterator {
-    fn new(dictionary: &Vec<Vec<String>>) -> Self {
-        let mut max_values: Vec<isize> = vec![];
-        for list in dictionary {
-            max_values.push((list.len() - 1) as isize)
-        }
-        let mut state: Vec<isize> = vec![];
-        for i in 0..dictionary.len() {
-            if i < dictionary.len() - 1 {
-                state.push(0);
-            } else {
-                state.push(-1)
-            }
-        }
+/// Search tier followed by the class index picked for every slot, finders emit positions in increasing order.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Position {
+    /// Resume with the combination at this position.
+    At(Vec<usize>),
+    /// Resume with the combination following this position.
+    After(Vec<usize>),
+}
 
-        DictionaryIterator {
-            max_values,
-            state,
+impl Position {
+    pub fn indices(&self) -> &[usize] {
+        match self {
+            Position::At(indices) | Position::After(indices) => indices,
         }
     }
 }
 
-impl Iterator for DictionaryIterator {
-    type Item = Vec<isize>;
+/// Expands a combination of anagram classes into every distinct word multiset it stands for.
+/// Repeated classes are adjacent and pick words in non-decreasing order.
+pub fn expand(combination: &[AnagramClass]) -> Vec<Vec<String>> {
+    let mut expanded: Vec<Vec<String>> = vec![vec![]];
+    for run in combination.chunk_by(|a, b| a == b) {
+        let choices = run[0].iter().combinations_with_replacement(run.len()).collect_vec();
+        expanded = expanded.into_iter()
+            .cartesian_product(choices)
+            .map(|(mut words, choice)| {
+                words.extend(choice.into_iter().cloned());
+                words
+            })
+            .collect();
+    }
+    expanded
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        for i in (0..self.max_values.len()).rev() {
-            if self.state.get(i) < self.max_values.get(i) {
-                if let Some(value) = self.state.get_mut(i) {
-                    *value += 1;
-                    return Some(self.state.clone());
-                }
-            } else {
-                if let Some(value) = self.state.get_mut(i) {
-                    *value = 0;
-                }
-            }
-        }
-        None
+/// Word multisets of `combination` not already tried in a lower tier, i.e. using at least one word of its rank.
+pub fn expand_ranked(combination: &Combination, ranks: &WordRanks) -> Vec<Vec<String>> {
+    let mut expanded = expand(&combination.classes);
+    if combination.rank > 0 {
+        expanded.retain(|words| words.iter().any(|word| ranks.get(word).is_some_and(|rank| *rank == combination.rank)));
     }
+    expanded
 }
 
+/// Searches word slots made of anagram classes, so words sharing a signature are tried once.
 pub struct CombinationFinder {
-    dictionary: Vec<Vec<String>>,
-    tx: Sender<Vec<String>>,
-    comparator: Vec<char>,
+    dictionary: Vec<Vec<AnagramClass>>,
+    tx: SyncSender<Combination>,
+    alphabet: Alphabet,
+    pool: Option<Signature>,
+    signatures: Vec<Vec<Option<Signature>>>,
+    same_as_previous: Vec<bool>,
     combination_rules: Vec<usize>,
+    cancel: CancellationToken,
+    pattern: usize,
+    rank: usize,
+    tiers: usize,
+    ranks: Option<WordRanks>,
+    fresh: Vec<Vec<bool>>,
+    fresh_ahead: Vec<bool>,
+    resume: Option<Position>,
+    progress: Option<Arc<Progress>>,
+    meter: Option<Arc<Meter>>,
+    tested: Cell<u64>,
 }
 
 impl CombinationFinder {
-    pub fn new(dictionary: Vec<Vec<String>>, tx: Sender<Vec<String>>, comparator: Vec<char>) -> Self {
+    pub fn new(dictionary: Vec<Vec<AnagramClass>>, tx: SyncSender<Combination>, comparator: Vec<char>) -> Self {
         let combination_rules: Vec<usize> = dictionary.iter().map(|x| { x.len() }).collect_vec();
+        let alphabet = Alphabet::new(&comparator);
+        let pool = alphabet.signature(comparator);
+        let signatures = CombinationFinder::signatures(&dictionary, &alphabet, &Normalizer::default());
+        let same_as_previous = (0..dictionary.len())
+            .map(|slot| slot > 0 && dictionary[slot] == dictionary[slot - 1])
+            .collect_vec();
         CombinationFinder {
             dictionary,
             tx,
-            comparator,
+            alphabet,
+            pool,
+            signatures,
+            same_as_previous,
             combination_rules,
+            cancel: CancellationToken::new(),
+            pattern: 0,
+            rank: 0,
+            tiers: 1,
+            ranks: None,
+            fresh: vec![],
+            fresh_ahead: vec![],
+            resume: None,
+            progress: None,
+            meter: None,
+            tested: Cell::new(0),
         }
     }
 
-    pub fn run(&mut self) -> Result<(), Error> {
+    /// Reads the letters of class words with `normalizer`, it must match the one the comparator was built with.
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.signatures = CombinationFinder::signatures(&self.dictionary, &self.alphabet, &normalizer);
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Tags emitted combinations with the index of the word length pattern being searched.
+    pub fn with_pattern(mut self, pattern: usize) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    /// Tags emitted combinations with the search tier `rank` out of `tiers`, the classes
+    /// must only hold words of that tier or a lower one.
+    pub fn with_rank(mut self, rank: usize, tiers: usize) -> Self {
+        self.rank = rank;
+        self.tiers = tiers;
+        self
+    }
+
+    /// Only searches combinations with a word of the current tier, the others were searched in a lower tier.
+    pub fn with_ranks(mut self, ranks: WordRanks) -> Self {
+        self.ranks = Some(ranks);
+        self
+    }
+
+    /// Skips every combination before `position`, a position of a later tier skips them all.
+    pub fn resume_from(mut self, position: Position) -> Self {
+        self.resume = Some(position);
+        self
+    }
+
+    /// Records every combination in `progress` before it is sent.
+    pub fn with_progress(mut self, progress: Arc<Progress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Reports the tuples tested and the share of the pattern searched to `meter`.
+    pub fn with_meter(mut self, meter: Arc<Meter>) -> Self {
+        self.meter = Some(meter);
+        self
+    }
+
+    /// Returns `false` when the search was cut short by cancellation or a dropped receiver.
+    pub fn run(&mut self) -> bool {
         info!("Finder is running, combination rules: {:?}", self.combination_rules);
-        self.comparator.sort();
-        self.find_combinations(SimpleFinder{});
-        info!("CombinationFinder for combination rules {:?} finished!", self.combination_rules);
-        Ok(())
+        let finished = self.find_combinations();
+        info!("CombinationFinder for combination rules {:?} finished: {}", self.combination_rules, finished);
+        finished
     }
 
-    fn is_valid(&self, combination: &Vec<String>) -> bool {
-        let mut combination = combination.join("").chars().collect::<Vec<char>>();
-        combination.sort();
-        self.comparator.eq(&combination)
+    fn find_combinations(&mut self) -> bool {
+        let resuming = match self.resume.as_ref().and_then(|position| position.indices().first()) {
+            Some(rank) if *rank > self.rank => return true,
+            Some(rank) => *rank == self.rank,
+            None => false,
+        };
+        let fresh = match &self.ranks {
+            Some(ranks) if self.rank > 0 => {
+                self.fresh = self.dictionary.iter()
+                    .map(|classes| classes.iter().map(|class| class.iter().any(|word| ranks.get(word) == Some(&self.rank))).collect_vec())
+                    .collect_vec();
+                self.fresh_ahead = (0..=self.dictionary.len())
+                    .map(|slot| self.fresh[slot..].iter().flatten().any(|fresh| *fresh))
+                    .collect_vec();
+                false
+            }
+            _ => true,
+        };
+        let finished = match &self.pool {
+            Some(pool) => {
+                let mut chosen = Vec::with_capacity(self.dictionary.len());
+                self.search(0, pool, &mut chosen, resuming, fresh)
+            }
+            None => true,
+        };
+        self.report_tested();
+        finished
     }
 
-    fn find_combinations<T: Finder>(&mut self, finder: T) {
-        let mut counter = DictionaryIterator::new(&self.dictionary);
-        while let Some(c) = counter.next() {
-            let words = finder.find(c, &self.dictionary);
-            if self.is_valid(&words) {
-                let _ = self.tx.send(words);
+    /// Picks a class for `slot` out of the letters still `remaining`, skipping the whole
+    /// subtree as soon as a class does not fit. Slots sharing the class list of the previous
+    /// slot never pick an earlier class, so every class multiset is emitted once.
+    /// While `resuming`, `chosen` is a prefix of the class indices of the resume position and earlier classes are skipped.
+    /// Until a class holding a word of the current tier is `fresh`ly picked, subtrees without one are skipped too.
+    /// Returns `false` once the receiver is gone or the search is cancelled.
+    fn search(&self, slot: usize, remaining: &Signature, chosen: &mut Vec<usize>, resuming: bool, fresh: bool) -> bool {
+        if self.cancel.is_cancelled() {
+            return false;
+        }
+        if !fresh && !self.fresh_ahead[slot] {
+            return true;
+        }
+        if slot == self.dictionary.len() {
+            if remaining.is_empty() && !(resuming && matches!(self.resume, Some(Position::After(_)))) {
+                let combination = Combination {
+                    pattern: self.pattern,
+                    rank: self.rank,
+                    position: std::iter::once(self.rank).chain(chosen.iter().copied()).collect(),
+                    classes: chosen.iter().enumerate().map(|(slot, i)| self.dictionary[slot][*i].clone()).collect_vec(),
+                };
+                if let Some(progress) = &self.progress {
+                    progress.emitted(&combination);
+                }
+                return self.tx.send(combination).is_ok();
+            }
+            return true;
+        }
+        let bound = match (&self.resume, resuming) {
+            (Some(position), true) => position.indices()[slot + 1],
+            _ => 0,
+        };
+        let first = if self.same_as_previous[slot] { chosen[slot - 1].max(bound) } else { bound };
+        for (i, signature) in self.signatures[slot].iter().enumerate().skip(first) {
+            self.tested.set(self.tested.get() + 1);
+            if let Some(rest) = signature.as_ref().and_then(|signature| remaining.checked_sub(signature)) {
+                chosen.push(i);
+                if slot < 2 {
+                    self.advance(chosen);
+                }
+                let running = self.search(slot + 1, &rest, chosen, resuming && i == bound, fresh || self.fresh[slot][i]);
+                chosen.pop();
+                if !running {
+                    return false;
+                }
             }
         }
+        true
     }
-}
 
-trait Finder {
-    fn find(&self, combination: Vec<isize>, dictionary: &Vec<Vec<String>>) -> Vec<String>;
-}
+    fn signatures(dictionary: &[Vec<AnagramClass>], alphabet: &Alphabet, normalizer: &Normalizer) -> Vec<Vec<Option<Signature>>> {
+        dictionary.iter()
+            .map(|classes| classes.iter().map(|class| class.first().and_then(|word| alphabet.signature(normalizer.letters(word)))).collect_vec())
+            .collect_vec()
+    }
 
-struct SimpleFinder {}
+    /// Estimates the share of the pattern searched from the tier and the classes picked for the first two slots.
+    fn advance(&self, chosen: &[usize]) {
+        if let Some(meter) = &self.meter {
+            let (searched, _) = chosen.iter().zip(&self.dictionary)
+                .fold((0.0, 1.0), |(searched, scale), (i, classes)| {
+                    let scale = scale / classes.len() as f64;
+                    (searched + *i as f64 * scale, scale)
+                });
+            meter.advance(self.pattern, (self.rank as f64 + searched) / self.tiers as f64);
+        }
+        self.report_tested();
+    }
 
-impl Finder for SimpleFinder {
-    fn find(&self, combination: Vec<isize>, dictionary: &Vec<Vec<String>>) -> Vec<String> {
-        let mut words: Vec<String> = vec![];
-        for (i, item) in combination.iter().enumerate() {
-            if let Some(word_list) = dictionary.get(i) {
-                if let Some(word) = word_list.get(*item as usize) {
-                    words.push(word.to_string());
-                }
-            }
+    /// Hands the tuples tested since the last report to the meter, counting locally keeps the
+    /// backtracking off the shared counter.
+    fn report_tested(&self) {
+        let tested = self.tested.take();
+        if let Some(meter) = &self.meter {
+            meter.tuples_tested(tested);
         }
-        words
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::sync::mpsc;
 
     use super::*;
     use test_case::test_case;
 
-    #[test_case(3, 3, 27)]
-    #[test_case(2, 3, 8)]
-    #[test_case(1, 8, 1)]
-    #[test_case(8, 1, 8)]
-    fn test_dictionary_iterator_creates_expected_number_of_combinations(rows: usize, columns: usize, expected_combinations: isize) {
-        let mut counter = 0;
-        let iterator = DictionaryIterator::new(&vec![vec!["a".to_string(); rows]; columns]);
+    const COMBINATIONS: usize = 100;
 
-        for _ in iterator {
-            counter += 1;
-        }
+    fn singletons(words: Vec<String>) -> Vec<AnagramClass> {
+        words.into_iter().map(|word| AnagramClass::from(vec![word])).collect()
+    }
 
-        assert_eq!(counter, expected_combinations);
+    fn group(words: &[String]) -> Vec<AnagramClass> {
+        words.iter()
+            .into_group_map_by(|word| word.chars().sorted().collect::<String>())
+            .into_iter()
+            .sorted()
+            .map(|(_, words)| AnagramClass::from(words.into_iter().cloned().sorted().collect_vec()))
+            .collect()
     }
 
-    #[test]
-    fn test_dictionary_iterator() {
-        let dictionary = vec![
-            vec![String::from("who"), String::from("bet"), String::from("set"), String::from("yet")],
-            vec![String::from("test"), String::from("best"), String::from("rest")],
-        ];
-        let mut iterator = DictionaryIterator::new(&dictionary);
-
-        assert_eq!(iterator.next().unwrap(), vec![0, 0]);
-        assert_eq!(iterator.next().unwrap(), vec![0, 1]);
-        assert_eq!(iterator.next().unwrap(), vec![0, 2]);
-        assert_eq!(iterator.next().unwrap(), vec![1, 0]);
-        assert_eq!(iterator.next().unwrap(), vec![1, 1]);
-        assert_eq!(iterator.next().unwrap(), vec![1, 2]);
-        assert_eq!(iterator.next().unwrap(), vec![2, 0]);
-        assert_eq!(iterator.next().unwrap(), vec![2, 1]);
-        assert_eq!(iterator.next().unwrap(), vec![2, 2]);
-        assert_eq!(iterator.next().unwrap(), vec![3, 0]);
-        assert_eq!(iterator.next().unwrap(), vec![3, 1]);
-        assert_eq!(iterator.next().unwrap(), vec![3, 2]);
-        assert_eq!(iterator.next(), None);
+    fn words(combination: Combination) -> Vec<String> {
+        combination.classes.iter().map(|class| class[0].clone()).collect()
     }
 
     #[test_case(vec![String::from("this"), String::from("is"), String::from("test")], vec!['e', 'h', 'i', 'i', 's', 's', 's', 't', 't', 't'], true)]
     #[test_case(vec![String::from("this"), String::from("is"), String::from("test")], vec!['e', 'i', 'i', 'i', 's', 's', 's', 't', 't', 't'], false)]
     fn validate_returns_expected(combination: Vec<String>, comparator: Vec<char>, expected: bool) {
-        let dictionary = vec![];
-        let (tx_res, _) = mpsc::channel();
-        let combination_finder = CombinationFinder::new(dictionary, tx_res, comparator);
-        assert_eq!(combination_finder.is_valid(&combination), expected);
+        let dictionary = combination.into_iter().map(|word| singletons(vec![word])).collect_vec();
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let mut combination_finder = CombinationFinder::new(dictionary, tx_res, comparator);
+        assert!(combination_finder.run());
+        assert_eq!(rx_res.try_recv().is_ok(), expected);
+    }
+
+    #[test]
+    fn run_emits_same_combinations_as_cartesian_product() {
+        let dictionary = [
+            ["sit", "its", "the", "tis"].map(String::from).to_vec(),
+            ["set", "hit", "ise", "tie"].map(String::from).to_vec(),
+            ["sith", "tees", "this", "tie"].map(String::from).to_vec(),
+        ];
+        let comparator = "itshittees".chars().collect::<Vec<char>>();
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let mut combination_finder = CombinationFinder::new(dictionary.iter().cloned().map(singletons).collect(), tx_res, comparator.clone());
+        assert!(combination_finder.run());
+        drop(combination_finder);
+
+        let mut expected = vec![];
+        for combination in dictionary.iter().map(|words| words.iter()).multi_cartesian_product() {
+            let mut letters = combination.iter().flat_map(|word| word.chars()).collect::<Vec<char>>();
+            let mut pool = comparator.clone();
+            letters.sort();
+            pool.sort();
+            if letters == pool {
+                expected.push(combination.into_iter().cloned().collect_vec());
+            }
+        }
+
+        assert!(!expected.is_empty());
+        assert_eq!(rx_res.iter().map(words).collect_vec(), expected);
+    }
+
+    #[test_case(2, "sitsit"; "two slots")]
+    #[test_case(3, "sitsitsit"; "three slots")]
+    #[test_case(3, "sitsitthe"; "three slots with distinct words")]
+    fn run_emits_each_word_multiset_once(slots: usize, pool: &str) {
+        let words = ["its", "sit", "the", "tis"].map(String::from).to_vec();
+        let dictionary = vec![group(&words); slots];
+        let comparator = pool.chars().collect::<Vec<char>>();
+        let mut sorted_pool = comparator.clone();
+        sorted_pool.sort();
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let mut combination_finder = CombinationFinder::new(dictionary, tx_res, comparator);
+        assert!(combination_finder.run());
+        drop(combination_finder);
+
+        let expected = words.iter()
+            .combinations_with_replacement(slots)
+            .filter(|combination| {
+                let mut letters = combination.iter().flat_map(|word| word.chars()).collect::<Vec<char>>();
+                letters.sort();
+                letters == sorted_pool
+            })
+            .count();
+        let emitted = rx_res.iter().flat_map(|combination| expand(&combination.classes)).map(|mut combination| {
+            combination.sort();
+            combination
+        }).collect_vec();
+
+        assert_eq!(emitted.len(), expected);
+        assert_eq!(emitted.iter().unique().count(), expected);
+    }
+
+    #[test_case(Position::At as fn(Vec<usize>) -> Position, 0; "at position")]
+    #[test_case(Position::After as fn(Vec<usize>) -> Position, 1; "after position")]
+    fn run_resumes_from_position(position: fn(Vec<usize>) -> Position, skipped: usize) {
+        let words = ["its", "sit", "the", "eth", "hit", "set", "tie"].map(String::from).to_vec();
+        let dictionary = vec![singletons(words.clone()); 3];
+        let comparator = "sitthehit".chars().collect::<Vec<char>>();
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        assert!(CombinationFinder::new(dictionary.clone(), tx_res, comparator.clone()).run());
+        let positions = rx_res.iter().map(|combination| combination.position).collect_vec();
+
+        for (i, start) in positions.iter().enumerate() {
+            let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+            let mut combination_finder = CombinationFinder::new(dictionary.clone(), tx_res, comparator.clone())
+                .resume_from(position(start.clone()));
+            assert!(combination_finder.run());
+            drop(combination_finder);
+
+            assert_eq!(rx_res.iter().map(|combination| combination.position).collect_vec(), positions[(i + skipped).min(positions.len())..]);
+        }
+    }
+
+    #[test_case(0, 2; "earlier tier")]
+    #[test_case(1, 1; "same tier")]
+    #[test_case(2, 0; "later tier")]
+    fn run_resumes_by_tier(resume_rank: usize, expected: usize) {
+        let words = ["its", "sit", "the"].map(String::from).to_vec();
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let mut combination_finder = CombinationFinder::new(vec![singletons(words); 2], tx_res, "sitthe".chars().collect())
+            .with_rank(1, 3)
+            .resume_from(Position::At(vec![resume_rank, 1, 2]));
+        assert!(combination_finder.run());
+        drop(combination_finder);
+
+        let combinations = rx_res.iter().collect_vec();
+
+        assert_eq!(combinations.len(), expected);
+        assert!(combinations.iter().all(|combination| combination.rank == 1 && combination.position[0] == 1));
+    }
+
+    #[test]
+    fn expand_ranked_skips_word_multisets_of_lower_tiers() {
+        let ranks = WordRanks::new(HashMap::from([(String::from("its"), 0), (String::from("sit"), 1), (String::from("the"), 0)]));
+        let combination = Combination {
+            pattern: 0,
+            rank: 1,
+            position: vec![1, 0, 0],
+            classes: vec![AnagramClass::from(vec![String::from("its"), String::from("sit")]), AnagramClass::from(vec![String::from("the")])],
+        };
+
+        assert_eq!(expand_ranked(&combination, &ranks), vec![vec!["sit", "the"]]);
+        assert_eq!(expand_ranked(&Combination { rank: 0, ..combination }, &ranks).len(), 2);
+    }
+
+    #[test]
+    fn run_with_ranks_skips_combinations_of_lower_tiers() {
+        let ranks = WordRanks::new(HashMap::from([
+            (String::from("its"), 0), (String::from("the"), 0), (String::from("eth"), 1), (String::from("hit"), 0), (String::from("set"), 0),
+        ]));
+        let words = ["its", "the", "eth", "hit", "set"].map(String::from).to_vec();
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let mut combination_finder = CombinationFinder::new(vec![group(&words); 2], tx_res, "sitthe".chars().collect())
+            .with_rank(1, 2)
+            .with_ranks(ranks.clone());
+        assert!(combination_finder.run());
+        drop(combination_finder);
+
+        let combinations = rx_res.iter().collect_vec();
+
+        assert_eq!(combinations.len(), 1);
+        assert_eq!(expand_ranked(&combinations[0], &ranks), vec![vec!["eth", "its"]]);
+
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let lower = ["its", "the"].map(String::from).to_vec();
+        let mut combination_finder = CombinationFinder::new(vec![singletons(lower); 2], tx_res, "sitthe".chars().collect())
+            .with_rank(1, 2)
+            .with_ranks(ranks);
+        assert!(combination_finder.run());
+        drop(combination_finder);
+
+        assert!(rx_res.iter().next().is_none());
+    }
+
+    #[test]
+    fn run_reports_tested_tuples_to_meter() {
+        let words = ["its", "sit", "the"].map(String::from).to_vec();
+        let meter = Arc::new(Meter::new(vec![9.0]));
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let mut combination_finder = CombinationFinder::new(vec![singletons(words); 2], tx_res, "sitthe".chars().collect())
+            .with_meter(meter.clone());
+        assert!(combination_finder.run());
+        drop(combination_finder);
+
+        assert_eq!(rx_res.iter().count(), 2);
+        assert_eq!(meter.snapshot(None).tuples, 3 + 3 + 2 + 1);
+    }
+
+    #[test]
+    fn run_reports_progress_to_meter() {
+        let words = ["its", "sit", "the", "eth", "hit", "set", "tie"].map(String::from).to_vec();
+        let meter = Arc::new(Meter::new(vec![343.0]));
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let mut combination_finder = CombinationFinder::new(vec![singletons(words); 3], tx_res, "sitthehit".chars().collect())
+            .with_meter(meter.clone());
+        assert!(combination_finder.run());
+        drop(combination_finder);
+
+        let snapshot = meter.snapshot(None);
+
+        assert!(snapshot.tuples > rx_res.iter().count() as u64);
+        assert!(snapshot.searched > 0.0 && snapshot.searched < 1.0);
+    }
+
+    #[test]
+    fn run_matches_words_by_normalized_letters() {
+        let dictionary = vec![singletons(vec![String::from("Café")]), singletons(vec![String::from("Bébé")])];
+        let normalizer = Normalizer::new().with_accent_stripping(true);
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let mut combination_finder = CombinationFinder::new(dictionary, tx_res, normalizer.letters("cafe bebe"))
+            .with_normalizer(normalizer);
+        assert!(combination_finder.run());
+
+        assert_eq!(words(rx_res.try_recv().unwrap()), vec![String::from("Café"), String::from("Bébé")]);
+    }
+
+    #[test]
+    fn run_stops_when_receiver_is_dropped() {
+        let words = ["sit", "its", "tis"].map(String::from).to_vec();
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        drop(rx_res);
+        let mut combination_finder = CombinationFinder::new(vec![group(&words), group(&words)], tx_res, "sitsit".chars().collect());
+
+        assert!(!combination_finder.run());
+    }
+
+    #[test]
+    fn run_stops_when_cancelled() {
+        let words = ["sit", "its", "tis"].map(String::from).to_vec();
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let cancel = CancellationToken::new();
+        let mut combination_finder = CombinationFinder::new(vec![group(&words), group(&words)], tx_res, "sitsit".chars().collect())
+            .with_cancellation(cancel.clone());
+
+        cancel.cancel();
+
+        assert!(!combination_finder.run());
+        assert!(rx_res.try_recv().is_err());
     }
 
     #[test]
     fn run_return_expected_combinations() {
         let dictionary = vec![
-            vec![String::from("who"), String::from("bet"), String::from("set")],
-            vec![String::from("test"), String::from("best"), String::from("pies")],
-            vec![String::from("dizzy"), String::from("junky"), String::from("zippy"), String::from("tyztp")],
+            singletons(vec![String::from("who"), String::from("bet"), String::from("set")]),
+            singletons(vec![String::from("test"), String::from("best"), String::from("pies")]),
+            singletons(vec![String::from("dizzy"), String::from("junky"), String::from("zippy"), String::from("tyztp")]),
         ];
-        let (tx_res, rx_res) = mpsc::channel();
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
         let mut combination_finder = CombinationFinder::new(dictionary, tx_res, vec!['e', 'h', 'i', 'o', 'p', 'p', 's', 't', 't', 'w', 'y', 'z']);
-        let _ = combination_finder.run();
+        assert!(combination_finder.run());
+
+        assert_eq!(words(rx_res.try_recv().unwrap()), vec![String::from("who"), String::from("test"), String::from("zippy")]);
+        assert_eq!(words(rx_res.try_recv().unwrap()), vec![String::from("who"), String::from("pies"), String::from("tyztp")]);
+    }
+
+    #[test]
+    fn run_searches_anagram_classes_once() {
+        let words = ["its", "sit", "tis", "the", "eth"].map(String::from).to_vec();
+        let (tx_res, rx_res) = mpsc::sync_channel(COMBINATIONS);
+        let mut combination_finder = CombinationFinder::new(vec![group(&words), group(&words)], tx_res, "sitthe".chars().collect());
+        assert!(combination_finder.run());
+        drop(combination_finder);
+
+        let combinations = rx_res.iter().collect_vec();
+
+        assert_eq!(combinations.len(), 1);
+        assert_eq!(expand(&combinations[0].classes).len(), 6);
+    }
+
+    #[test]
+    fn expand_picks_repeated_class_words_in_non_decreasing_order() {
+        let sit = AnagramClass::from(vec![String::from("its"), String::from("sit")]);
+        let the = AnagramClass::from(vec![String::from("the")]);
+
+        let expanded = expand(&[sit.clone(), sit, the]);
 
-        assert_eq!(rx_res.try_recv().unwrap(), vec![String::from("who"), String::from("test"), String::from("zippy")]);
-        assert_eq!(rx_res.try_recv().unwrap(), vec![String::from("who"), String::from("pies"), String::from("tyztp")]);
+        assert_eq!(expanded, vec![
+            vec!["its", "its", "the"],
+            vec!["its", "sit", "the"],
+            vec!["sit", "sit", "the"],
+        ]);
     }
 }
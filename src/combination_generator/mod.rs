@@ -5,6 +5,7 @@ pub struct Generator {
     min_word_len: usize,
     max_word_len: usize,
     state: Vec<usize>,
+    started: bool,
 }
 
 impl Generator {
@@ -14,7 +15,8 @@ impl Generator {
             target_len,
             min_word_len,
             max_word_len,
-            state
+            state,
+            started: false,
         }
     }
 
@@ -37,7 +39,7 @@ impl Generator {
                 *value += 1;
                 return Ok(());
             } else {
-                *value = 1;
+                *value = self.min_word_len;
             }
         }
         self.state = vec![];
@@ -49,6 +51,12 @@ impl Iterator for Generator {
     type Item = Vec<usize>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if self.is_valid() {
+                return Some(self.state.clone());
+            }
+        }
         while self.increment() == Ok(()) {
             if self.state.iter().sum::<usize>() == self.target_len && self.is_valid() {
                 return Some(self.state.clone());
@@ -106,8 +114,8 @@ mod tests {
     use test_case::test_case;
 
     #[test_case(vec![2, 2, 2], vec![3, 2, 2])]
-    #[test_case(vec![6, 2, 2], vec![1, 3, 2])]
-    #[test_case(vec![6, 6, 2], vec![1, 1, 3])]
+    #[test_case(vec![6, 2, 2], vec![2, 3, 2])]
+    #[test_case(vec![6, 6, 2], vec![2, 2, 3])]
     #[test_case(vec![4, 6, 6], vec![5, 6, 6])]
     fn increment_correct_combination_element(state: Vec<usize>, expected: Vec<usize>) {
         let mut generator = Generator::new(10, 2, 6, 3);
@@ -137,6 +145,21 @@ mod tests {
         assert_eq!(generator.next().unwrap(), vec![9, 9, 3]);
     }
 
+    #[test_case(6, 2, 3, vec![2, 2, 2]; "every word of minimum length")]
+    #[test_case(6, 6, 1, vec![6]; "one word of minimum length")]
+    fn get_next_combination_starts_with_minimum_lengths(target_len: usize, min_word_len: usize, max_words: usize, expected: Vec<usize>) {
+        let mut generator = Generator::new(target_len, min_word_len, 10, max_words);
+
+        assert_eq!(generator.next().unwrap(), expected);
+    }
+
+    #[test]
+    fn combination_generator_yields_minimum_length_patterns() {
+        let combinations = CombinationGenerator::new(4, 2, 6, 2).collect::<Vec<Vec<usize>>>();
+
+        assert_eq!(combinations, vec![vec![4], vec![2, 2]]);
+    }
+
     #[test]
     fn combination_generator_calls_generators_in_turns() {
         let mut combination_generator = CombinationGenerator::new(8, 2, 6, 3);
@@ -148,10 +171,10 @@ mod tests {
 
     #[test]
     fn combination_generator_returns_expected_number_of_combinations() {
-        let mut combination_length_gen = CombinationGenerator::new(18, 3, 10, 4);
+        let combination_length_gen = CombinationGenerator::new(18, 3, 10, 4);
         let mut combinations = vec![];
 
-        while let Some(combination) = combination_length_gen.next() {
+        for combination in combination_length_gen {
             combinations.push(combination);
         }
 
@@ -1,58 +1,260 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Error};
-use std::fs::File;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::cleaner::CleanRules;
+use crate::normalizer::Normalizer;
+use crate::signature::{Alphabet, Signature};
+
+/// Words sharing one letter signature, interchangeable in any anagram.
+pub type AnagramClass = Arc<[String]>;
+
+/// Search tier of every dictionary word, 0 for words of the best ranked sources.
+pub type WordRanks = Arc<HashMap<String, usize>>;
+
+/// Word list file, or directory of word list files, a dictionary is merged from.
+/// Words of sources with a lower `rank` are searched first, `tag` names the source of a word.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DictionarySource {
+    pub path: PathBuf,
+    pub tag: String,
+    pub rank: usize,
+}
+
+impl DictionarySource {
+    /// Source of rank 0 tagged with the file name of `path` without its extension.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        let path = path.into();
+        let tag = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_else(|| path.display().to_string());
+        DictionarySource { path, tag, rank: 0 }
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = tag.to_string();
+        self
+    }
+
+    pub fn with_rank(mut self, rank: usize) -> Self {
+        self.rank = rank;
+        self
+    }
+
+    /// Files the words are read from, for a directory every file in it except hidden ones, by name.
+    pub fn files(&self) -> Result<Vec<PathBuf>, Error> {
+        if !self.path.is_dir() {
+            return Ok(vec![self.path.clone()]);
+        }
+        let mut files = vec![];
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            if path.is_file() && !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+impl fmt::Display for DictionarySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}={}", self.tag, self.rank, self.path.display())
+    }
+}
+
+impl FromStr for DictionarySource {
+    type Err = String;
+
+    /// Parses `[TAG[@RANK]=]PATH`, e.g. `jargon@1=lists/jargon.txt`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((name, path)) = s.split_once('=') else {
+            return Ok(DictionarySource::new(s));
+        };
+        let (tag, rank) = match name.split_once('@') {
+            Some((tag, rank)) => (tag, rank.parse::<usize>().map_err(|e| format!("invalid rank '{}' of dictionary '{}': {}", rank, s, e))?),
+            None => (name, 0),
+        };
+        if tag.is_empty() || path.is_empty() {
+            return Err(format!("expected [TAG[@RANK]=]PATH, got '{}'", s));
+        }
+        Ok(DictionarySource::new(path).with_tag(tag).with_rank(rank))
+    }
+}
 
 pub struct Dictionary {
-    words: HashMap<usize, HashSet<String>>
+    words: HashMap<usize, HashSet<String>>,
+    alphabet: Alphabet,
+    normalizer: Normalizer,
+    anagrams: HashMap<Signature, AnagramClass>,
+    tags: HashMap<String, Vec<String>>,
+    ranks: WordRanks,
+    tiers: usize,
 }
 
 impl Dictionary {
-    pub fn new(file_path: &str, min_word_len: usize, max_word_len: usize, allowed_chars: &Vec<char>) -> Result<Self, Error> {
-        let file = File::open(file_path).unwrap();
-        let mut reader = BufReader::new(file);
-        let dictionary = Dictionary::map(&mut reader, min_word_len, max_word_len, allowed_chars);
+    /// Merges the words of `sources`, cleaned by `clean_rules`, that fit into `allowed_chars` as normalized
+    /// by `normalizer`. Words keep their cleaned spelling but are keyed by their number of normalized letters.
+    /// A word listed by several sources is kept once, with the tags of all of them and the best rank.
+    /// Distinct spellings, e.g. `abé` and `abe`, are all kept as members of the same anagram class.
+    /// Errors name the file that could not be read.
+    pub fn new(
+        sources: &[DictionarySource],
+        min_word_len: usize,
+        max_word_len: usize,
+        allowed_chars: &Vec<char>,
+        normalizer: &Normalizer,
+        clean_rules: &CleanRules,
+    ) -> Result<Self, (PathBuf, Error)> {
+        let mut dictionary: HashMap<usize, HashSet<String>> = HashMap::new();
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        let mut source_ranks: HashMap<String, usize> = HashMap::new();
+        for source in sources {
+            for path in source.files().map_err(|e| (source.path.clone(), e))? {
+                let mut reader = BufReader::new(File::open(&path).map_err(|e| (path.clone(), e))?);
+                for (len, words) in Dictionary::map(&mut reader, min_word_len, max_word_len, allowed_chars, normalizer, clean_rules) {
+                    for word in words {
+                        let word_tags = tags.entry(word.clone()).or_default();
+                        if !word_tags.contains(&source.tag) {
+                            word_tags.push(source.tag.clone());
+                        }
+                        source_ranks.entry(word.clone()).and_modify(|rank| *rank = source.rank.min(*rank)).or_insert(source.rank);
+                        dictionary.entry(len).or_default().insert(word);
+                    }
+                }
+            }
+        }
+        let (ranks, tiers) = Dictionary::rank_tiers(source_ranks);
+        let alphabet = Alphabet::new(allowed_chars);
+        let anagrams = Dictionary::index(&alphabet, normalizer, &dictionary);
         Ok(Dictionary {
             words: dictionary,
+            alphabet,
+            normalizer: *normalizer,
+            anagrams,
+            tags,
+            ranks: Arc::new(ranks),
+            tiers,
         })
     }
 
     pub fn get(&self, key: &usize) -> Option<HashSet<String>> {
-        match self.words.get(key) {
-            Some(set) => Some(set.clone()),
-            _ => None
+        self.words.get(key).cloned()
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub fn signature(&self, word: &str) -> Option<Signature> {
+        self.alphabet.signature(self.normalizer.letters(word))
+    }
+
+    /// Words sharing `signature`, i.e. anagrams of each other, in alphabetical order.
+    pub fn anagrams(&self, signature: &Signature) -> Option<&AnagramClass> {
+        self.anagrams.get(signature)
+    }
+
+    /// Tags of the sources listing `word`, in source order.
+    pub fn tags(&self, word: &str) -> &[String] {
+        self.tags.get(word).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Search tier of `word`, tiers number the distinct ranks of the sources from the best one.
+    pub fn rank(&self, word: &str) -> usize {
+        self.ranks.get(word).copied().unwrap_or_default()
+    }
+
+    pub fn word_ranks(&self) -> WordRanks {
+        self.ranks.clone()
+    }
+
+    /// Number of search tiers, at least 1.
+    pub fn tiers(&self) -> usize {
+        self.tiers
+    }
+
+    /// Anagram classes of words with `len` letters and a tier up to `rank`, ordered by signature.
+    pub fn ranked_classes(&self, len: usize, rank: usize) -> Vec<AnagramClass> {
+        self.classes(len).into_iter()
+            .filter_map(|class| {
+                if class.iter().all(|word| self.rank(word) <= rank) {
+                    return Some(class);
+                }
+                let words = class.iter().filter(|word| self.rank(word) <= rank).cloned().collect::<Vec<String>>();
+                (!words.is_empty()).then(|| AnagramClass::from(words))
+            })
+            .collect()
+    }
+
+    /// Anagram classes of words with `len` letters, ordered by signature.
+    pub fn classes(&self, len: usize) -> Vec<AnagramClass> {
+        self.signatures(len).into_iter().map(|signature| self.anagrams[signature].clone()).collect()
+    }
+
+    /// Distinct signatures of words with `len` letters.
+    pub fn signatures(&self, len: usize) -> Vec<&Signature> {
+        let mut signatures = self.anagrams.keys().filter(|signature| signature.letters() == len).collect::<Vec<&Signature>>();
+        signatures.sort();
+        signatures
+    }
+
+    /// Replaces the best source rank of every word by its tier, the index of that rank among
+    /// the ranks of all words, so skipped ranks do not add empty tiers.
+    fn rank_tiers(source_ranks: HashMap<String, usize>) -> (HashMap<String, usize>, usize) {
+        let mut distinct = source_ranks.values().copied().collect::<Vec<usize>>();
+        distinct.sort();
+        distinct.dedup();
+        let ranks = source_ranks.into_iter()
+            .map(|(word, rank)| (word, distinct.binary_search(&rank).unwrap_or_default()))
+            .collect();
+        (ranks, distinct.len().max(1))
+    }
+
+    fn index(alphabet: &Alphabet, normalizer: &Normalizer, dictionary: &HashMap<usize, HashSet<String>>) -> HashMap<Signature, AnagramClass> {
+        let mut anagrams: HashMap<Signature, Vec<String>> = HashMap::new();
+        for word in dictionary.values().flatten() {
+            if let Some(signature) = alphabet.signature(normalizer.letters(word)) {
+                anagrams.entry(signature).or_default().push(word.clone());
+            }
         }
+        anagrams.into_iter()
+            .map(|(signature, mut words)| {
+                words.sort();
+                (signature, AnagramClass::from(words))
+            })
+            .collect()
     }
 
-    fn map<T: BufRead>(reader: &mut T, min_word_len: usize, max_word_len: usize, allowed_chars: &Vec<char>) -> HashMap<usize, HashSet<String>> {
+    fn map<T: BufRead>(
+        reader: &mut T,
+        min_word_len: usize,
+        max_word_len: usize,
+        allowed_chars: &Vec<char>,
+        normalizer: &Normalizer,
+        clean_rules: &CleanRules,
+    ) -> HashMap<usize, HashSet<String>> {
         let mut dictionary: HashMap<usize, HashSet<String>> = HashMap::new();
 
-        for line in reader.lines() {
-            if let Ok(mut word) = line {
-                if Self::is_valid(min_word_len, max_word_len, allowed_chars, &word) {
-                    let word = Dictionary::clean(&mut word).to_string();
-                    let word_len = word.len();
-                    if dictionary.contains_key(&word_len) {
-                        if let Some(set) = dictionary.get_mut(&word_len) {
-                            set.insert(word);
-                        }
-                    }
-                    else {
-                        dictionary.insert(word_len, HashSet::from([word]));
-                    }
-                }
+        for line in reader.lines().map_while(Result::ok) {
+            let Some(word) = clean_rules.clean(&line) else { continue };
+            if Self::is_valid(normalizer, min_word_len, max_word_len, allowed_chars, &word) {
+                dictionary.entry(normalizer.len(&word)).or_default().insert(word);
             }
         }
         dictionary
     }
 
-    fn is_valid(min_word_len: usize, max_word_len: usize, allowed_chars: &Vec<char>, word: &str) -> bool {
-        let excluded_chars = "'";
-        if word.len() < min_word_len || word.len() > max_word_len { return false };
-        if word.chars().any(char::is_numeric) { return false }
-        if excluded_chars.chars().map(|x| word.contains(x)).collect::<Vec<bool>>().contains(&true) { return false };
+    fn is_valid(normalizer: &Normalizer, min_word_len: usize, max_word_len: usize, allowed_chars: &Vec<char>, word: &str) -> bool {
+        if !normalizer.is_word(word) { return false };
+        let letters = normalizer.letters(word);
+        if letters.len() < min_word_len || letters.len() > max_word_len { return false };
 
         let mut letter_count: HashMap<char, usize> = HashMap::new();
-        for c in word.chars() {
+        for c in letters {
             *letter_count.entry(c).or_insert(1) += 1;
         }
         let mut allowed_chars_count: HashMap<char, usize> = HashMap::new();
@@ -70,10 +272,6 @@ impl Dictionary {
         }
         true
     }
-
-    fn clean(word: &mut str) -> &str {
-       word.trim_end_matches(|x| char::is_alphabetic(x) == false)
-    }
 }
 
 #[cfg(test)]
@@ -90,7 +288,135 @@ mod tests {
         ]);
         let allowed_chars = vec!['i', 't', 't', 'e', 's', 'h', 'j', 'u', 'w', 'r', 'o', 'k'];
 
-        assert_eq!(Dictionary::map(&mut wordlist.as_bytes(), 4, 8, &allowed_chars), expected);
+        assert_eq!(Dictionary::map(&mut wordlist.as_bytes(), 4, 8, &allowed_chars, &Normalizer::new(), &CleanRules::default()), expected);
+    }
+
+    #[test]
+    fn map_folds_possessives_of_wordlist_into_their_stem() {
+        let wordlist = "a\na's\nabacus\nabacus's\nabé's\nabés\napliqué's\n";
+        let allowed_chars = Normalizer::new().letters("a abacus abés apliqué");
+
+        let dictionary = Dictionary::map(&mut wordlist.as_bytes(), 1, 8, &allowed_chars, &Normalizer::new(), &CleanRules::default());
+
+        assert_eq!(dictionary[&1], HashSet::from(["a".to_string()]));
+        assert_eq!(dictionary[&3], HashSet::from(["abé".to_string()]));
+        assert_eq!(dictionary[&4], HashSet::from(["abés".to_string()]));
+        assert_eq!(dictionary[&6], HashSet::from(["abacus".to_string()]));
+        assert_eq!(dictionary[&7], HashSet::from(["apliqué".to_string()]));
+    }
+
+    #[test]
+    fn map_drops_possessives_when_kept() {
+        let rules = CleanRules { strip_possessive: false, ..CleanRules::default() };
+
+        let dictionary = Dictionary::map(&mut "abacus's\n".as_bytes(), 1, 8, &"abacuss".chars().collect(), &Normalizer::new(), &rules);
+
+        assert!(dictionary.is_empty());
+    }
+
+    #[test]
+    fn new_loads_wordlist() {
+        let allowed_chars = Normalizer::new().letters("poultry outwits ants");
+        let dictionary = Dictionary::new(&[DictionarySource::new("wordlist")], 2, 10, &allowed_chars, &Normalizer::new(), &CleanRules::default()).unwrap();
+
+        assert!(dictionary.get(&8).unwrap().contains("printout"));
+        assert!(dictionary.get(&5).unwrap().iter().all(|word| !word.contains('\'')));
+    }
+
+    #[test]
+    fn new_merges_sources_with_tags_and_tiers() {
+        let dir = std::env::temp_dir().join(format!("anagram-dictionary-{}", std::process::id()));
+        fs::create_dir_all(dir.join("names")).unwrap();
+        fs::write(dir.join("core.txt"), "sit\nits\nthe\n").unwrap();
+        fs::write(dir.join("jargon.txt"), "tis\nThe\nthe\n").unwrap();
+        fs::write(dir.join("names").join("a.txt"), "Tess\n").unwrap();
+        fs::write(dir.join("names").join(".hidden"), "hit\n").unwrap();
+        let sources = [
+            DictionarySource::new(dir.join("core.txt")),
+            DictionarySource::new(dir.join("jargon.txt")).with_rank(5),
+            DictionarySource::new(dir.join("names")).with_rank(9),
+        ];
+        let allowed_chars = "sitthesis".chars().collect::<Vec<char>>();
+
+        let dictionary = Dictionary::new(&sources, 3, 8, &allowed_chars, &Normalizer::new(), &CleanRules::default());
+        let _ = fs::remove_dir_all(&dir);
+        let dictionary = dictionary.unwrap();
+
+        assert_eq!(dictionary.tiers(), 3);
+        assert_eq!(dictionary.tags("the"), ["core", "jargon"]);
+        assert_eq!(dictionary.tags("Tess"), ["names"]);
+        assert!(dictionary.tags("hit").is_empty());
+        assert_eq!(dictionary.tags("The"), ["jargon"]);
+        assert_eq!(*dictionary.classes(3).into_iter().find(|class| class.contains(&String::from("the"))).unwrap(), ["The", "the"]);
+        assert_eq!(["its", "the", "tis", "Tess"].map(|word| dictionary.rank(word)), [0, 0, 1, 2]);
+        let sit = |rank| dictionary.ranked_classes(3, rank).into_iter().find(|class| class.contains(&String::from("sit"))).unwrap();
+        assert_eq!(*sit(0), ["its", "sit"]);
+        assert_eq!(*sit(1), ["its", "sit", "tis"]);
+        assert!(dictionary.ranked_classes(4, 1).is_empty());
+        assert_eq!(dictionary.ranked_classes(4, 2), dictionary.classes(4));
+    }
+
+    #[test]
+    fn new_names_missing_source_file() {
+        let sources = [DictionarySource::new("testlist"), DictionarySource::new("missing-wordlist")];
+
+        let error = Dictionary::new(&sources, 2, 8, &vec!['a'], &Normalizer::new(), &CleanRules::default()).err().unwrap();
+
+        assert_eq!(error.0, PathBuf::from("missing-wordlist"));
+    }
+
+    #[test_case("wordlist", DictionarySource { path: PathBuf::from("wordlist"), tag: String::from("wordlist"), rank: 0 }; "path")]
+    #[test_case("lists/jargon.txt", DictionarySource { path: PathBuf::from("lists/jargon.txt"), tag: String::from("jargon"), rank: 0 }; "tag from file name")]
+    #[test_case("names@2=lists/names", DictionarySource { path: PathBuf::from("lists/names"), tag: String::from("names"), rank: 2 }; "tag and rank")]
+    #[test_case("core=wordlist", DictionarySource { path: PathBuf::from("wordlist"), tag: String::from("core"), rank: 0 }; "tag")]
+    fn source_parses(spec: &str, expected: DictionarySource) {
+        assert_eq!(spec.parse::<DictionarySource>(), Ok(expected));
+    }
+
+    #[test_case("names@x=lists/names"; "invalid rank")]
+    #[test_case("=wordlist"; "empty tag")]
+    #[test_case("core="; "empty path")]
+    fn source_rejects_malformed_spec(spec: &str) {
+        assert!(spec.parse::<DictionarySource>().is_err());
+    }
+
+    #[test]
+    fn map_keeps_original_spelling_keyed_by_letter_count() {
+        let wordlist = "Poultry\ncafé\ncafe\u{301}\nFAÇADE\n";
+        let allowed_chars = Normalizer::new().letters("poultry café façade");
+
+        let dictionary = Dictionary::map(&mut wordlist.as_bytes(), 3, 8, &allowed_chars, &Normalizer::new(), &CleanRules::default());
+
+        assert_eq!(dictionary[&7], HashSet::from(["Poultry".to_string()]));
+        assert_eq!(dictionary[&4], HashSet::from(["café".to_string(), "cafe\u{301}".to_string()]));
+        assert_eq!(dictionary[&6], HashSet::from(["FAÇADE".to_string()]));
+    }
+
+    #[test]
+    fn signature_strips_accents_when_asked() {
+        let wordlist = "café\nface\n";
+        let normalizer = Normalizer::new().with_accent_stripping(true);
+        let allowed_chars = normalizer.letters("face");
+        let words = Dictionary::map(&mut wordlist.as_bytes(), 2, 8, &allowed_chars, &normalizer, &CleanRules::default());
+        let alphabet = Alphabet::new(&allowed_chars);
+
+        let anagrams = Dictionary::index(&alphabet, &normalizer, &words);
+
+        assert_eq!(*anagrams[&alphabet.signature("face".chars()).unwrap()], ["café", "face"]);
+    }
+
+    #[test]
+    fn index_groups_anagrams_by_signature() {
+        let wordlist = "sits\ntiss\nsit\nits\nthe\nhit";
+        let allowed_chars = "sitthesis".chars().collect::<Vec<char>>();
+        let words = Dictionary::map(&mut wordlist.as_bytes(), 2, 8, &allowed_chars, &Normalizer::new(), &CleanRules::default());
+        let alphabet = Alphabet::new(&allowed_chars);
+
+        let anagrams = Dictionary::index(&alphabet, &Normalizer::new(), &words);
+
+        assert_eq!(anagrams.len(), 4);
+        assert_eq!(*anagrams[&alphabet.signature("tiss".chars()).unwrap()], ["sits", "tiss"]);
+        assert_eq!(*anagrams[&alphabet.signature("its".chars()).unwrap()], ["its", "sit"]);
     }
 
     #[test_case("valid", "validdt", true; "valid word")]
@@ -100,17 +426,11 @@ mod tests {
     #[test_case("conta1n", "contain", false; "contain digit")]
     #[test_case("seveeen", "neeeves", true; "max length")]
     #[test_case("cat", "catr", true; "min length")]
+    #[test_case("Cat", "catr", true; "capitalized")]
+    #[test_case("cafés", "cafés", true; "accent counts as one letter")]
+    #[test_case("café", "cafe", false; "accent kept apart from base letter")]
     fn is_valid_returns_correct_bool(word: &str, allowed_chars: &str, expected: bool) {
-        let allowed_chars = allowed_chars.chars().into_iter().collect::<Vec<char>>();
-        assert_eq!(Dictionary::is_valid(3, 7, &allowed_chars, word), expected);
-    }
-
-    #[test_case("word!", "word")]
-    #[test_case("work34", "work")]
-    #[test_case("worst;", "worst")]
-    #[test_case("mors\n", "mors")]
-    #[test_case("something?", "something")]
-    fn clean_returns_word_with_removed_non_alphabetic_chars(word: &str, expected: &str) {
-        assert_eq!(Dictionary::clean(&mut word.to_string()), expected);
+        let allowed_chars = allowed_chars.chars().collect::<Vec<char>>();
+        assert_eq!(Dictionary::is_valid(&Normalizer::new(), 3, 7, &allowed_chars, word), expected);
     }
 }
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::progress::{format_count, format_duration};
+
+/// Size of the search space of one word length pattern. Both counts are upper bounds,
+/// the letters of most word tuples do not add up to the phrase.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PatternEstimate {
+    pub pattern: Vec<usize>,
+    /// Product of the number of words of every length in the pattern.
+    pub tuples: f64,
+    /// Candidate phrases hashed: every word multiset the finder searches in all word orders and renderings.
+    pub permutations: f64,
+}
+
+impl PatternEstimate {
+    /// `words[len]` is the number of dictionary words of length `len`.
+    pub fn new(pattern: Vec<usize>, words: &[f64], renderings: usize) -> Self {
+        let count = |len: usize| words.get(len).copied().unwrap_or(0.0);
+        let tuples = pattern.iter().map(|len| count(*len)).product();
+        let multisets = pattern.chunk_by(|a, b| a == b)
+            .map(|run| multisets(count(run[0]), run.len()))
+            .product::<f64>();
+        let orders = (1..=pattern.len()).map(|n| n as f64).product::<f64>();
+        PatternEstimate {
+            pattern,
+            tuples,
+            permutations: multisets * orders * renderings as f64,
+        }
+    }
+}
+
+/// Number of ways to pick `k` out of `n` words with repetition.
+fn multisets(n: f64, k: usize) -> f64 {
+    (0..k).map(|i| (n + i as f64) / (i + 1) as f64).product()
+}
+
+/// Size of the search space of a solve run, with the hashing rate measured by calibration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    pub patterns: Vec<PatternEstimate>,
+    /// Candidate phrases all hash workers test per second.
+    pub rate: Option<f64>,
+}
+
+impl Estimate {
+    pub fn tuples(&self) -> f64 {
+        self.patterns.iter().map(|pattern| pattern.tuples).sum()
+    }
+
+    pub fn permutations(&self) -> f64 {
+        self.patterns.iter().map(|pattern| pattern.permutations).sum()
+    }
+
+    /// Time to hash every permutation at the calibrated rate.
+    pub fn duration(&self) -> Option<Duration> {
+        self.rate
+            .filter(|rate| *rate > 0.0)
+            .and_then(|rate| Duration::try_from_secs_f64(self.permutations() / rate).ok())
+    }
+}
+
+/// Serializes with the totals and the estimated time, `duration_secs` is `null` without a calibrated rate.
+impl Serialize for Estimate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut estimate = serializer.serialize_struct("Estimate", 5)?;
+        estimate.serialize_field("patterns", &self.patterns)?;
+        estimate.serialize_field("tuples", &self.tuples())?;
+        estimate.serialize_field("permutations", &self.permutations())?;
+        estimate.serialize_field("rate", &self.rate)?;
+        estimate.serialize_field("duration_secs", &self.duration().map(|duration| duration.as_secs_f64()))?;
+        estimate.end()
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<24} {:>12} {:>14}", "pattern", "tuples", "permutations")?;
+        for pattern in &self.patterns {
+            let lengths = pattern.pattern.iter().map(usize::to_string).collect::<Vec<String>>().join(" ");
+            writeln!(f, "{:<24} {:>12} {:>14}", lengths, format_count(pattern.tuples), format_count(pattern.permutations))?;
+        }
+        write!(f, "{:<24} {:>12} {:>14}", "total", format_count(self.tuples()), format_count(self.permutations()))?;
+        match (self.rate, self.duration()) {
+            (Some(rate), Some(duration)) => write!(f, "\nestimated time: {} at {} candidates/s", format_duration(duration), format_count(rate)),
+            (Some(rate), None) => write!(f, "\nestimated time: unbounded at {} candidates/s", format_count(rate)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use test_case::test_case;
+
+    const WORDS: [f64; 6] = [0.0, 0.0, 0.0, 10.0, 20.0, 5.0];
+
+    #[test_case(vec![3, 4], 200.0, 400.0; "distinct lengths")]
+    #[test_case(vec![4, 4], 400.0, 420.0; "repeated length")]
+    #[test_case(vec![3, 3, 5], 500.0, 1650.0; "repeated run and distinct length")]
+    #[test_case(vec![2, 5], 0.0, 0.0; "length without words")]
+    fn pattern_estimate_returns_expected(pattern: Vec<usize>, tuples: f64, permutations: f64) {
+        let estimate = PatternEstimate::new(pattern, &WORDS, 1);
+
+        assert_eq!(estimate.tuples, tuples);
+        assert_eq!(estimate.permutations, permutations);
+    }
+
+    #[test]
+    fn estimate_sums_patterns_and_extrapolates_rate() {
+        let estimate = Estimate {
+            patterns: vec![PatternEstimate::new(vec![3, 4], &WORDS, 2), PatternEstimate::new(vec![4, 3], &WORDS, 2)],
+            rate: Some(100.0),
+        };
+
+        assert_eq!(estimate.tuples(), 400.0);
+        assert_eq!(estimate.permutations(), 1600.0);
+        assert_eq!(estimate.duration(), Some(Duration::from_secs(16)));
+        assert!(estimate.to_string().ends_with("estimated time: 16s at 100 candidates/s"));
+    }
+
+    #[test]
+    fn estimate_serializes_with_totals() {
+        let estimate = Estimate { patterns: vec![PatternEstimate::new(vec![3, 4], &WORDS, 1)], rate: None };
+
+        assert_eq!(serde_json::to_value(&estimate).unwrap(), json!({
+            "patterns": [{ "pattern": [3, 4], "tuples": 200.0, "permutations": 400.0 }],
+            "tuples": 200.0,
+            "permutations": 400.0,
+            "rate": null,
+            "duration_secs": null,
+        }));
+    }
+}
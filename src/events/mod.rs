@@ -0,0 +1,158 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+
+use crate::hasher::HashAlgorithm;
+use crate::password::Password;
+use crate::progress::Snapshot;
+use crate::renderer::Rendering;
+use crate::solver::{Coverage, Solution};
+
+/// Callback receiving the events of a run as they happen, called from worker threads.
+pub type EventSink = Arc<dyn Fn(&Event) + Send + Sync>;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// `rank` is the search tier, every pattern is searched once per tier.
+    PatternStarted { pattern: Vec<usize>, rank: usize },
+    /// `complete` is `false` when the search of the pattern was cut short.
+    PatternFinished { pattern: Vec<usize>, rank: usize, complete: bool },
+    Found(Found),
+    Stats(Snapshot),
+}
+
+/// Password found by a run, `target` is its index in the target list. `restored` marks passwords
+/// read from the potfile or a checkpoint when the run starts.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Found {
+    pub target: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    pub digest: String,
+    pub algorithm: HashAlgorithm,
+    pub phrase: String,
+    pub words: Vec<String>,
+    pub pattern: Vec<usize>,
+    pub rendering: Option<Rendering>,
+    #[serde(rename = "elapsed_secs", serialize_with = "optional_secs")]
+    pub elapsed: Option<Duration>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub restored: bool,
+}
+
+impl Found {
+    pub fn new(target: usize, password: &Password) -> Option<Self> {
+        Some(Found {
+            target,
+            labels: password.labels().to_vec(),
+            digest: password.digest().to_string(),
+            algorithm: password.algorithm()?,
+            phrase: password.phrase()?.to_string(),
+            words: password.words().to_vec(),
+            pattern: password.pattern(),
+            rendering: password.rendering().cloned(),
+            elapsed: password.elapsed(),
+            restored: false,
+        })
+    }
+}
+
+/// Outcome of a solve run, with the digests still missing.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    #[serde(rename = "elapsed_secs", serialize_with = "secs")]
+    pub elapsed: Duration,
+    pub interrupted: bool,
+    pub candidates: u64,
+    pub coverage: Coverage,
+    pub found: Vec<Found>,
+    pub missing: Vec<String>,
+}
+
+impl From<&Solution> for Summary {
+    fn from(solution: &Solution) -> Self {
+        Summary {
+            elapsed: solution.elapsed,
+            interrupted: solution.interrupted,
+            candidates: solution.candidates,
+            coverage: solution.coverage.clone(),
+            found: solution.passwords.iter().enumerate().filter_map(|(target, password)| Found::new(target, password)).collect(),
+            missing: solution.passwords.iter().filter(|password| !password.is_found()).map(|password| password.digest().to_string()).collect(),
+        }
+    }
+}
+
+pub(crate) fn secs<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+fn optional_secs<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => secs(duration, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn found() -> Password {
+        let mut password = Password::new(HashAlgorithm::Md5, md5::compute(b"this is test").to_vec());
+        let words = ["this", "is", "test"].map(String::from).to_vec();
+        password.mark_found("this is test", words, HashAlgorithm::Md5, Some(Rendering::default()), Some(Duration::from_millis(1500)));
+        password
+    }
+
+    #[test]
+    fn found_event_serializes_as_tagged_object() {
+        let event = Event::Found(Found::new(0, &found()).unwrap());
+
+        assert_eq!(serde_json::to_value(&event).unwrap(), json!({
+            "event": "found",
+            "target": 0,
+            "digest": "8c6d115258631625b625486f81b09532",
+            "algorithm": "md5",
+            "phrase": "this is test",
+            "words": ["this", "is", "test"],
+            "pattern": [4, 2, 4],
+            "rendering": { "separator": " ", "casing": "lower", "trailing": "" },
+            "elapsed_secs": 1.5,
+        }));
+    }
+
+    #[test]
+    fn restored_found_event_is_marked() {
+        let found = Found { restored: true, ..Found::new(0, &found()).unwrap() };
+
+        assert_eq!(serde_json::to_value(Event::Found(found)).unwrap()["restored"], json!(true));
+    }
+
+    #[test]
+    fn pattern_events_name_the_pattern() {
+        let event = Event::PatternFinished { pattern: vec![4, 2, 4], rank: 0, complete: true };
+
+        assert_eq!(serde_json::to_string(&event).unwrap(), r#"{"event":"pattern_finished","pattern":[4,2,4],"rank":0,"complete":true}"#);
+    }
+
+    #[test]
+    fn summary_lists_found_and_missing_targets() {
+        let missing = Password::new(HashAlgorithm::Md5, md5::compute(b"not an anagram").to_vec());
+        let solution = Solution {
+            passwords: vec![found(), missing.clone()],
+            elapsed: Duration::from_secs(2),
+            coverage: Coverage::default(),
+            candidates: 10,
+            interrupted: false,
+        };
+
+        let summary = Summary::from(&solution);
+
+        assert_eq!(summary.found.len(), 1);
+        assert_eq!(summary.missing, vec![missing.digest().to_string()]);
+        assert_eq!(serde_json::to_value(&summary).unwrap()["elapsed_secs"], json!(2.0));
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sha1::Digest;
+
+pub trait Hasher: Send + Sync {
+    fn digest(&self, input: &[u8]) -> Vec<u8>;
+
+    fn output_len(&self) -> usize;
+
+    fn block_size(&self) -> usize {
+        64
+    }
+}
+
+pub struct Md5;
+
+impl Hasher for Md5 {
+    fn digest(&self, input: &[u8]) -> Vec<u8> {
+        md5::compute(input).to_vec()
+    }
+
+    fn output_len(&self) -> usize {
+        16
+    }
+}
+
+pub struct Sha1;
+
+impl Hasher for Sha1 {
+    fn digest(&self, input: &[u8]) -> Vec<u8> {
+        sha1::Sha1::digest(input).to_vec()
+    }
+
+    fn output_len(&self) -> usize {
+        20
+    }
+}
+
+pub struct Sha256;
+
+impl Hasher for Sha256 {
+    fn digest(&self, input: &[u8]) -> Vec<u8> {
+        sha2::Sha256::digest(input).to_vec()
+    }
+
+    fn output_len(&self) -> usize {
+        32
+    }
+}
+
+pub struct Sha512;
+
+impl Hasher for Sha512 {
+    fn digest(&self, input: &[u8]) -> Vec<u8> {
+        sha2::Sha512::digest(input).to_vec()
+    }
+
+    fn output_len(&self) -> usize {
+        64
+    }
+
+    fn block_size(&self) -> usize {
+        128
+    }
+}
+
+pub struct Blake3;
+
+impl Hasher for Blake3 {
+    fn digest(&self, input: &[u8]) -> Vec<u8> {
+        blake3::hash(input).as_bytes().to_vec()
+    }
+
+    fn output_len(&self) -> usize {
+        32
+    }
+}
+
+pub struct Crc32;
+
+impl Hasher for Crc32 {
+    fn digest(&self, input: &[u8]) -> Vec<u8> {
+        crc32fast::hash(input).to_be_bytes().to_vec()
+    }
+
+    fn output_len(&self) -> usize {
+        4
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    Blake3,
+    Crc32,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 6] = [
+        HashAlgorithm::Md5,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Sha512,
+        HashAlgorithm::Blake3,
+        HashAlgorithm::Crc32,
+    ];
+
+    pub fn hasher(&self) -> &'static dyn Hasher {
+        match self {
+            HashAlgorithm::Md5 => &Md5,
+            HashAlgorithm::Sha1 => &Sha1,
+            HashAlgorithm::Sha256 => &Sha256,
+            HashAlgorithm::Sha512 => &Sha512,
+            HashAlgorithm::Blake3 => &Blake3,
+            HashAlgorithm::Crc32 => &Crc32,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Crc32 => "crc32",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase().replace('-', "");
+        HashAlgorithm::ALL.iter()
+            .find(|algorithm| algorithm.name() == name)
+            .copied()
+            .ok_or_else(|| format!("unknown hash algorithm '{}', expected one of: {}", s, HashAlgorithm::ALL.map(|a| a.name()).join(", ")))
+    }
+}
+
+/// Describes how a candidate phrase is turned into the digest of a target.
+/// The phrase is wrapped in `prefix` and `suffix`, hashed (as HMAC when `hmac_key` is set)
+/// and every further iteration hashes the lowercase hex digest of the previous one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HashTemplate {
+    pub prefix: String,
+    pub suffix: String,
+    pub hmac_key: Option<Vec<u8>>,
+    pub iterations: usize,
+}
+
+impl Default for HashTemplate {
+    fn default() -> Self {
+        HashTemplate {
+            prefix: String::new(),
+            suffix: String::new(),
+            hmac_key: None,
+            iterations: 1,
+        }
+    }
+}
+
+impl HashTemplate {
+    pub fn apply(&self, algorithm: HashAlgorithm, phrase: &str) -> Vec<u8> {
+        let hasher = algorithm.hasher();
+        let input = format!("{}{}{}", self.prefix, phrase, self.suffix);
+        let mut digest = self.hash(hasher, input.as_bytes());
+        for _ in 1..self.iterations {
+            digest = self.hash(hasher, hex::encode(&digest).as_bytes());
+        }
+        digest
+    }
+
+    fn hash(&self, hasher: &dyn Hasher, input: &[u8]) -> Vec<u8> {
+        match &self.hmac_key {
+            Some(key) => hmac(hasher, key, input),
+            None => hasher.digest(input),
+        }
+    }
+}
+
+fn hmac(hasher: &dyn Hasher, key: &[u8], message: &[u8]) -> Vec<u8> {
+    let block_size = hasher.block_size();
+    let mut key = if key.len() > block_size { hasher.digest(key) } else { key.to_vec() };
+    key.resize(block_size, 0);
+
+    let mut inner = key.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>();
+    inner.extend_from_slice(message);
+    let mut outer = key.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>();
+    outer.extend(hasher.digest(&inner));
+    hasher.digest(&outer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72")]
+    #[test_case(HashAlgorithm::Sha1, "a9993e364706816aba3e25717850c26c9cd0d89d")]
+    #[test_case(HashAlgorithm::Sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")]
+    #[test_case(HashAlgorithm::Sha512, "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f")]
+    #[test_case(HashAlgorithm::Blake3, "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85")]
+    #[test_case(HashAlgorithm::Crc32, "352441c2")]
+    fn digest_returns_known_answer(algorithm: HashAlgorithm, expected: &str) {
+        let hasher = algorithm.hasher();
+        let digest = hasher.digest(b"abc");
+
+        assert_eq!(hex::encode(&digest), expected);
+        assert_eq!(digest.len(), hasher.output_len());
+    }
+
+    #[test_case(HashAlgorithm::Md5, "80070713463e7749b90c2dc24911e275")]
+    #[test_case(HashAlgorithm::Sha1, "de7c9b85b8b78aa6bc8a7a36f70a90701c9db4d9")]
+    #[test_case(HashAlgorithm::Sha256, "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8")]
+    #[test_case(HashAlgorithm::Sha512, "b42af09057bac1e2d41708e48a902e09b5ff7f12ab428a4fe86653c73dd248fb82f948a549f7b791a5b41915ee4d1ec3935357e4e2317250d0372afa2ebeeb3a")]
+    fn template_with_hmac_key_returns_known_answer(algorithm: HashAlgorithm, expected: &str) {
+        let template = HashTemplate { hmac_key: Some(b"key".to_vec()), ..HashTemplate::default() };
+
+        assert_eq!(hex::encode(template.apply(algorithm, "The quick brown fox jumps over the lazy dog")), expected);
+    }
+
+    #[test]
+    fn template_wraps_phrase_in_prefix_and_suffix() {
+        let template = HashTemplate { prefix: String::from("a"), suffix: String::from("c"), ..HashTemplate::default() };
+
+        assert_eq!(template.apply(HashAlgorithm::Md5, "b"), HashAlgorithm::Md5.hasher().digest(b"abc"));
+    }
+
+    #[test]
+    fn template_iterations_hash_previous_hex_digest() {
+        let template = HashTemplate { iterations: 2, ..HashTemplate::default() };
+        let first = hex::encode(md5::compute(b"abc").0);
+
+        assert_eq!(template.apply(HashAlgorithm::Md5, "abc"), md5::compute(first.as_bytes()).to_vec());
+    }
+
+    #[test_case("md5", Ok(HashAlgorithm::Md5))]
+    #[test_case("SHA-256", Ok(HashAlgorithm::Sha256))]
+    #[test_case("blake3", Ok(HashAlgorithm::Blake3))]
+    #[test_case("whirlpool", Err(()))]
+    fn from_str_returns_expected(name: &str, expected: Result<HashAlgorithm, ()>) {
+        assert_eq!(name.parse::<HashAlgorithm>().map_err(|_| ()), expected);
+    }
+}
@@ -0,0 +1,41 @@
+#![cfg_attr(test, feature(test))]
+#[cfg(test)]
+extern crate test;
+
+pub mod cancellation;
+pub mod checkpoint;
+pub mod cleaner;
+pub mod combination_finder;
+pub mod combination_generator;
+pub mod dictionary;
+pub mod estimate;
+pub mod events;
+pub mod hasher;
+pub mod normalizer;
+pub mod password;
+pub mod permutations_finder;
+pub mod phrase;
+pub mod potfile;
+pub mod progress;
+pub mod renderer;
+pub mod signature;
+pub mod solver;
+pub mod target_list;
+pub mod targets;
+
+pub use cancellation::CancellationToken;
+pub use cleaner::CleanRules;
+pub use dictionary::DictionarySource;
+pub use estimate::{Estimate, PatternEstimate};
+pub use events::{Event, EventSink, Found, Summary};
+pub use hasher::{HashAlgorithm, HashTemplate, Hasher};
+pub use normalizer::Normalizer;
+pub use password::Password;
+pub use phrase::Phrase;
+pub use potfile::{PotEntry, Potfile};
+pub use progress::{ProgressMode, Snapshot};
+pub use renderer::{Casing, RenderRules, Rendering};
+pub use solver::{Coverage, Solution, Solver, SolverBuilder, SolverError};
+pub use target_list::{TargetFormat, TargetSpec};
+
+#[macro_use] extern crate log;
@@ -1,160 +1,170 @@
-#![feature(test)]
-extern crate test;
-
-use std::env;
-use std::io;
-use std::io::Error;
-use std::sync::Arc;
-use std::sync::Mutex;
-use std::sync::mpsc;
-use std::thread;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::process;
 use std::time::Duration;
-use dictionary::Dictionary;
 
-use combination_generator::CombinationGenerator;
-use combination_finder::CombinationFinder;
-use itertools::Itertools;
-use permutations_finder::PermutationsFinder;
+use anagram_solver_rust::{CancellationToken, Password, Potfile, ProgressMode, Rendering, SolverBuilder, SolverError, Summary};
+use clap::Parser;
+use cli::{AnagramsArgs, Cli, Command, OutputFormat, ShowArgs, SolveArgs};
 
-mod combination_finder;
-mod combination_generator;
-mod dictionary;
-mod permutations_finder;
+mod cli;
 
 #[macro_use] extern crate log;
 
-pub struct Password {
-    phrase: String,
-    digest: String,
-    found: bool,
-}
+/// Exit status of a run stopped by SIGINT or SIGTERM, as shells report for SIGINT.
+const EXIT_INTERRUPTED: i32 = 130;
 
-impl Password {
-    fn new(digest: String) -> Self {
-        Password{
-            phrase: String::new(),
-            digest,
-            found: false,
-        }
-    }
-}
+/// Time between redraws of the progress bar.
+const BAR_REFRESH: Duration = Duration::from_millis(500);
+const BAR_WIDTH: usize = 30;
 
-fn main() -> io::Result<()> {
+fn main() {
     env_logger::init();
-    info!("Starting...");
-    let min_word_len = 2;
-    let max_word_len = 10;
-    let target = 18;
-    let max_words = 4;
-    let passwords = Arc::new(Mutex::new(vec![
-        Password::new("e4820b45d2277f3844eac66c903e84be".to_string()),
-        Password::new("23170acc097c24edb98fc5488ab033fe".to_string()),
-        Password::new("665e5bcb0c20062fe8abaaf4628bb154".to_string()),
-    ]));
-    let comparator = vec!["poultry", "outwits", "ants"].join("").chars().collect::<Vec<char>>();
-    let args: Vec<String> = env::args().collect();
-    let max_workers = 6;
-
-    run(&args[1], min_word_len, max_word_len, comparator, target, max_words, passwords, max_workers);
-    info!("Done");
-    Ok(())
+    let cli = Cli::parse();
+    let cancel = CancellationToken::new();
+    let handler_cancel = cancel.clone();
+    let handler = ctrlc::set_handler(move || {
+        if handler_cancel.is_cancelled() {
+            process::exit(EXIT_INTERRUPTED);
+        }
+        eprintln!("interrupted, stopping workers (repeat to exit immediately)");
+        handler_cancel.cancel();
+    });
+    if let Err(e) = handler {
+        warn!("Cannot handle termination signals: {}", e);
+    }
+    let result = match cli.command {
+        Command::Solve(args) => solve(*args, cancel.clone()),
+        Command::Anagrams(args) => anagrams(args, cancel.clone()),
+        Command::Show(args) => show(args),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+    if cancel.is_cancelled() {
+        process::exit(EXIT_INTERRUPTED);
+    }
 }
 
-fn run(dictionary_path: &str, min_word_len: usize, max_word_len: usize, comparator: Vec<char>, target_length: usize, max_words: usize, passwords: Arc<Mutex<Vec<Password>>>, max_workers: usize) {
-    let min_word_len_mutex = Arc::new(Mutex::new(min_word_len));
-    let max_word_len_mutex = Arc::new(Mutex::new(max_word_len));
-    let passwords_cp = passwords.clone();
-
-    if let Ok(dictionary) = Dictionary::new(&dictionary_path, *Arc::clone(&min_word_len_mutex).lock().unwrap(), *Arc::clone(&max_word_len_mutex).lock().unwrap(), &comparator) {
-        info!("Dictionary loaded successfully!");
-        let (comb_tx, comb_rx) = mpsc::channel();
-
-        let mut combination_length_gen = CombinationGenerator::new(
-            target_length,
-            min_word_len,
-            max_word_len,
-            max_words
-        );
-        let (finder_tx, finder_rx) = mpsc::channel();
-        let thread_no = Arc::new(Mutex::new(0));
-        let thread_no_cp = thread_no.clone();
-
-        thread::spawn(move || {
-             loop {
-                 if *thread_no_cp.lock().unwrap() < max_workers {
-                     if let Some(combination) = combination_length_gen.next() {
-                         info!("Combination {:?}", combination);
-                         let mut words: Vec<Vec<String>> = vec![];
-                         for word_len in combination {
-                             if let Some(words_list) = dictionary.get(&word_len) {
-                                 let mut list: Vec<String> = vec![];
-                                 for word in words_list {
-                                     list.push(word.clone());
-                                 }
-                                 words.push(list);
-                             } else {
-                                 info!("Selected word length {} not found! Skipping...", word_len);
-                                 continue;
-                             }
-                         }
-                         let comb_tx_cp = comb_tx.clone();
-                         let comparator = comparator.clone();
-                         let finder_tx = finder_tx.clone();
-                         let _ = thread::spawn(move || {
-                             let _ = finder_tx.send(CombinationFinder::new(words, comb_tx_cp, comparator).run());
-                         });
-                         *thread_no_cp.lock().unwrap() += 1;
-                     }
-                 }
-                 if let Ok(_) = finder_rx.try_recv() {
-                     *thread_no_cp.lock().unwrap() -= 1;
-                 }
-
-                 if *thread_no_cp.lock().unwrap() == 0 {
-                     break;
-                 }
+fn solve(args: SolveArgs, cancel: CancellationToken) -> Result<(), SolverError> {
+    let builder = args.builder().map_err(SolverError::InvalidConfig)?.cancellation(cancel);
+    let mode = match args.progress {
+        ProgressMode::Auto if io::stderr().is_terminal() => ProgressMode::Bar,
+        ProgressMode::Auto => ProgressMode::Off,
+        mode => mode,
+    };
+    let builder = match args.format {
+        OutputFormat::Jsonl => builder.events(|event| {
+            let mut stdout = io::stdout().lock();
+            if serde_json::to_writer(&mut stdout, event).is_ok() {
+                let _ = writeln!(stdout);
             }
-        });
-
-        let (permutation_tx, permutation_rx) = mpsc::channel();
-        let permutation_tx = permutation_tx.clone();
-        thread::spawn(move || {
-            let mut permutations_finder = PermutationsFinder::new(passwords_cp, comb_rx);
-            let _ = permutation_tx.send(permutations_finder.run());
-        });
-
-        loop {
-            thread::sleep(Duration::new(1, 0));
-            if let Ok(_) = permutation_rx.try_recv() {
-                break;
+        }),
+        OutputFormat::Text | OutputFormat::Json => builder,
+    };
+    let solver = progress(builder, mode, Duration::from_secs(args.progress_interval)).build()?;
+    if args.dry_run {
+        let calibration = args.calibrate
+            .map(|secs| Duration::try_from_secs_f64(secs).map_err(|e| SolverError::InvalidConfig(format!("invalid calibration time: {}", e))))
+            .transpose()?;
+        let estimate = solver.estimate(calibration)?;
+        let mut stdout = io::stdout().lock();
+        match args.format {
+            OutputFormat::Text => writeln!(stdout, "{}", estimate).map_err(SolverError::Output)?,
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut stdout, &estimate).map_err(|e| SolverError::Output(e.into()))?;
+                writeln!(stdout).map_err(SolverError::Output)?;
             }
-            else if *thread_no.lock().unwrap() == 0 {
-                break;
+            OutputFormat::Jsonl => {
+                serde_json::to_writer(&mut stdout, &estimate).map_err(|e| SolverError::Output(e.into()))?;
+                writeln!(stdout).map_err(SolverError::Output)?;
             }
         }
-        let result = passwords.lock().unwrap();
-        let passwords = result.iter().map(|password| {password.phrase.clone()}).collect_vec();
-        info!("Found passwords: {:?}", passwords);
-    } else {
-        Error::new(io::ErrorKind::InvalidInput, "Cannot process dictionary!");
+        return Ok(());
     }
+    info!("Starting...");
+    let solution = solver.solve()?;
+    if mode == ProgressMode::Bar {
+        eprintln!();
+    }
+    match args.format {
+        OutputFormat::Text => solution.passwords.iter().for_each(print_password),
+        OutputFormat::Json => {
+            let mut stdout = io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &Summary::from(&solution)).map_err(|e| SolverError::Output(e.into()))?;
+            writeln!(stdout).map_err(SolverError::Output)?;
+        }
+        OutputFormat::Jsonl => {}
+    }
+    if solution.interrupted {
+        eprintln!(
+            "interrupted after {:.1?}: found {} of {} passwords, {}, {} candidates tested",
+            solution.elapsed, solution.found().count(), solution.passwords.len(), solution.coverage, solution.candidates
+        );
+    } else if !solution.coverage.is_exhaustive() {
+        eprintln!("stopped early: {}", solution.coverage);
+    }
+    info!("Done in {:?}", solution.elapsed);
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_run() {
-        let passwords = Arc::new(Mutex::new(vec![
-            Password::new("8c6d115258631625b625486f81b09532".to_string()),
-            Password::new("d1e744eced74494c7a5292a93252eb6c".to_string()),
-        ]));
-        let comparator = vec!["sitt", "thesis"].join("").chars().collect::<Vec<char>>();
+fn show(args: ShowArgs) -> Result<(), SolverError> {
+    let potfile = Potfile::load(&args.potfile).map_err(|e| SolverError::Potfile(args.potfile.clone(), e))?;
+    let mut passwords = args.targets.passwords().map_err(SolverError::InvalidConfig)?;
+    if passwords.is_empty() {
+        return Err(SolverError::InvalidConfig(String::from("no target digests given")));
+    }
+    let known = potfile.restore(&mut passwords);
+    passwords.iter().for_each(print_password);
+    info!("{} of {} targets known", known, passwords.len());
+    Ok(())
+}
 
-        run("testlist", 2, 10, comparator, 10, 4, passwords.clone(), 2);
+fn print_password(password: &Password) {
+    if !password.labels().is_empty() {
+        println!("{} -> {}", password.labels().join(", "), password.phrase().unwrap_or("-"));
+        return;
+    }
+    match password.algorithm() {
+        Some(algorithm) => match password.rendering().filter(|rendering| **rendering != Rendering::default()) {
+            Some(rendering) => println!("{}:{} {} ({})", algorithm, password.digest(), password.phrase().unwrap_or("-"), rendering),
+            None => println!("{}:{} {}", algorithm, password.digest(), password.phrase().unwrap_or("-")),
+        },
+        None => println!("{} -", password.digest()),
+    }
+}
 
-        assert_eq!(passwords.lock().unwrap()[0].phrase, "this is test");
-        assert_eq!(passwords.lock().unwrap()[1].phrase, "sit the sits");
+/// Progress goes to stderr, so it never mixes with the passwords printed on stdout.
+fn progress(builder: SolverBuilder, mode: ProgressMode, interval: Duration) -> SolverBuilder {
+    match mode {
+        ProgressMode::Bar => builder.progress(BAR_REFRESH, |snapshot| {
+            eprint!("\r\x1b[2K{}", snapshot.bar(BAR_WIDTH));
+            let _ = io::stderr().flush();
+        }),
+        ProgressMode::Lines => builder.progress(interval, |snapshot| eprintln!("progress: {}", snapshot)),
+        ProgressMode::Auto | ProgressMode::Off => builder,
     }
 }
+
+fn anagrams(args: AnagramsArgs, cancel: CancellationToken) -> Result<(), SolverError> {
+    let solver = args.search.builder().cancellation(cancel).build()?;
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path).map_err(SolverError::Output)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let count = solver.anagrams(args.permutations, args.limit, |words, tags| {
+        if !args.tags {
+            return writeln!(output, "{}", words.join(" "));
+        }
+        let tagged = words.iter().zip(tags).map(|(word, tags)| format!("{}[{}]", word, tags.join(","))).collect::<Vec<String>>();
+        writeln!(output, "{}", tagged.join(" "))
+    });
+    // A reader that stops early, e.g. `head`, closes the pipe: that ends the listing, it is not an error.
+    let count = match count.and_then(|count| output.flush().map(|_| count).map_err(SolverError::Output)) {
+        Err(SolverError::Output(e)) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+        count => count?,
+    };
+    info!("Listed {} anagrams", count);
+    Ok(())
+}
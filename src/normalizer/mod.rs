@@ -0,0 +1,79 @@
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Turns phrases and dictionary words into the letters anagrams are made of: lowercase,
+/// composed, one letter per grapheme and, with `strip_accents`, without diacritics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Normalizer {
+    strip_accents: bool,
+}
+
+impl Normalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches accented letters with their base letter, so "café" fits the letters of "face".
+    pub fn with_accent_stripping(mut self, strip_accents: bool) -> Self {
+        self.strip_accents = strip_accents;
+        self
+    }
+
+    /// Letters of `text` in order, anything but letters is dropped.
+    pub fn letters(&self, text: &str) -> Vec<char> {
+        let folded = if self.strip_accents {
+            text.nfd().filter(|c| !is_combining_mark(*c)).collect::<String>().to_lowercase()
+        } else {
+            text.to_lowercase()
+        };
+        folded.nfc().collect::<String>()
+            .graphemes(true)
+            .filter_map(|grapheme| grapheme.chars().next())
+            .filter(|c| c.is_alphabetic())
+            .collect()
+    }
+
+    /// Number of letters of `text`, each grapheme counts once.
+    pub fn len(&self, text: &str) -> usize {
+        self.letters(text).len()
+    }
+
+    /// Whether `word` is made of letters only, accents and other combining marks included.
+    pub fn is_word(&self, word: &str) -> bool {
+        !word.is_empty() && word.nfd().all(|c| c.is_alphabetic() || is_combining_mark(c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("Poultry", false, "poultry"; "case folding")]
+    #[test_case("café", false, "café"; "precomposed accent")]
+    #[test_case("cafe\u{301}", false, "café"; "combining accent is composed")]
+    #[test_case("Crème Brûlée!", true, "cremebrulee"; "accents stripped")]
+    #[test_case("ÅNGSTRÖM", true, "angstrom"; "uppercase accents stripped")]
+    fn letters_returns_expected(text: &str, strip_accents: bool, expected: &str) {
+        let normalizer = Normalizer::new().with_accent_stripping(strip_accents);
+
+        assert_eq!(normalizer.letters(text).into_iter().collect::<String>(), expected);
+    }
+
+    #[test_case("café", 4; "precomposed")]
+    #[test_case("cafe\u{301}", 4; "decomposed")]
+    #[test_case("naïve", 5; "diaeresis")]
+    fn len_counts_graphemes(text: &str, expected: usize) {
+        assert_eq!(Normalizer::new().len(text), expected);
+        assert_eq!(Normalizer::new().with_accent_stripping(true).len(text), expected);
+    }
+
+    #[test_case("Café", true)]
+    #[test_case("cafe\u{301}", true)]
+    #[test_case("it's", false)]
+    #[test_case("conta1n", false)]
+    fn is_word_returns_expected(word: &str, expected: bool) {
+        assert_eq!(Normalizer::new().is_word(word), expected);
+    }
+}
@@ -0,0 +1,270 @@
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD};
+use std::time::Duration;
+
+use base64::Engine;
+use itertools::Itertools;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::hasher::{HashAlgorithm, HashTemplate};
+use crate::renderer::Rendering;
+
+/// Ids of the crypt(3) schemes found in shadow files, e.g. `$1$` for md5-crypt and `$6$` for sha512-crypt.
+const CRYPT_IDS: [&str; 11] = ["1", "2", "2a", "2b", "2x", "2y", "5", "6", "7", "y", "gy"];
+
+#[derive(Clone, Debug)]
+pub struct Password {
+    phrase: String,
+    digest: String,
+    candidates: Vec<(HashAlgorithm, Vec<u8>)>,
+    template: HashTemplate,
+    algorithm: Option<HashAlgorithm>,
+    rendering: Option<Rendering>,
+    words: Vec<String>,
+    elapsed: Option<Duration>,
+    labels: Vec<String>,
+    found: bool,
+}
+
+impl Password {
+    pub fn new(algorithm: HashAlgorithm, digest: Vec<u8>) -> Self {
+        Password{
+            phrase: String::new(),
+            digest: hex::encode(&digest),
+            candidates: vec![(algorithm, digest)],
+            template: HashTemplate::default(),
+            algorithm: None,
+            rendering: None,
+            words: vec![],
+            elapsed: None,
+            labels: vec![],
+            found: false,
+        }
+    }
+
+    /// Parses a target digest in hex or base64. The algorithm is taken from an `sha256:` or
+    /// `$sha256$` prefix, then from `algorithm`, and otherwise inferred from the digest length,
+    /// keeping every algorithm the digest could belong to.
+    /// Real crypt(3) strings such as `$6$salt$hash` are rejected, they are salted key derivations.
+    pub fn parse(spec: &str, algorithm: Option<HashAlgorithm>) -> Result<Self, String> {
+        let spec = spec.trim();
+        if let Some(id) = spec.strip_prefix('$').and_then(|rest| rest.split('$').next()).filter(|id| CRYPT_IDS.contains(id)) {
+            return Err(format!("'{}' is a crypt(3) hash (${}$), crypt(3) formats are not supported; use $name$digest with a plain digest", spec, id));
+        }
+        let (algorithm, digest) = match Password::split_prefix(spec) {
+            Some((name, digest)) => (Some(name.parse::<HashAlgorithm>()?), digest),
+            None => (algorithm, spec),
+        };
+        let candidates = Password::decode(digest).into_iter()
+            .flat_map(|bytes| {
+                HashAlgorithm::ALL.iter()
+                    .filter(|candidate| algorithm.is_none_or(|algorithm| algorithm == **candidate))
+                    .filter(|candidate| candidate.hasher().output_len() == bytes.len())
+                    .map(|candidate| (*candidate, bytes.clone()))
+                    .collect::<Vec<(HashAlgorithm, Vec<u8>)>>()
+            })
+            .collect::<Vec<(HashAlgorithm, Vec<u8>)>>();
+        if candidates.is_empty() {
+            return Err(match algorithm {
+                Some(algorithm) => format!(
+                    "'{}' is not a valid {} digest (expected {} hex characters or base64)",
+                    digest, algorithm, algorithm.hasher().output_len() * 2
+                ),
+                None => format!("cannot detect the hash algorithm of '{}'", digest),
+            });
+        }
+        Ok(Password{
+            phrase: String::new(),
+            digest: digest.to_string(),
+            candidates,
+            template: HashTemplate::default(),
+            algorithm: None,
+            rendering: None,
+            words: vec![],
+            elapsed: None,
+            labels: vec![],
+            found: false,
+        })
+    }
+
+    pub fn with_template(mut self, template: HashTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Names the target, e.g. the user the digest belongs to.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.labels.push(label.to_string());
+        self
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Whether both passwords stand for the same digest hashed the same way.
+    pub fn is_same_target(&self, other: &Password) -> bool {
+        self.candidates == other.candidates && self.template == other.template
+    }
+
+    /// Takes over the labels of a duplicate of this password.
+    pub fn merge(&mut self, duplicate: Password) {
+        for label in duplicate.labels {
+            if !self.labels.contains(&label) {
+                self.labels.push(label);
+            }
+        }
+    }
+
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    /// Algorithms the digest may have been produced with.
+    pub fn candidates(&self) -> Vec<HashAlgorithm> {
+        self.candidates.iter().map(|(algorithm, _)| *algorithm).unique().collect()
+    }
+
+    pub fn template(&self) -> &HashTemplate {
+        &self.template
+    }
+
+    /// Algorithm that produced the digest, known once the password is found.
+    pub fn algorithm(&self) -> Option<HashAlgorithm> {
+        self.algorithm
+    }
+
+    /// Separator, casing and trailing text of the matched phrase.
+    pub fn rendering(&self) -> Option<&Rendering> {
+        self.rendering.as_ref()
+    }
+
+    /// Dictionary words of the matched phrase in the order they were rendered.
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Word length pattern of the matched phrase.
+    pub fn pattern(&self) -> Vec<usize> {
+        self.words.iter().map(|word| word.graphemes(true).count()).collect()
+    }
+
+    /// Time from the start of the run to the match, unknown for passwords restored from a checkpoint.
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+
+    pub fn phrase(&self) -> Option<&str> {
+        if self.found { Some(&self.phrase) } else { None }
+    }
+
+    pub fn is_found(&self) -> bool {
+        self.found
+    }
+
+    pub(crate) fn digests(&self) -> impl Iterator<Item = &(HashAlgorithm, Vec<u8>)> {
+        self.candidates.iter()
+    }
+
+    /// Records the matched phrase, `rendering` and `words` are unknown for matches read from a potfile.
+    pub(crate) fn mark_found(&mut self, phrase: &str, words: Vec<String>, algorithm: HashAlgorithm, rendering: Option<Rendering>, elapsed: Option<Duration>) {
+        self.phrase = phrase.to_string();
+        self.words = words;
+        self.algorithm = Some(algorithm);
+        self.rendering = rendering;
+        self.elapsed = elapsed;
+        self.found = true;
+    }
+
+    fn split_prefix(spec: &str) -> Option<(&str, &str)> {
+        if let Some(rest) = spec.strip_prefix('$') {
+            return rest.split_once('$');
+        }
+        spec.split_once(':')
+    }
+
+    fn decode(digest: &str) -> Vec<Vec<u8>> {
+        let mut decoded: Vec<Vec<u8>> = vec![];
+        let attempts = [
+            hex::decode(digest).ok(),
+            STANDARD.decode(digest).ok(),
+            STANDARD_NO_PAD.decode(digest).ok(),
+            URL_SAFE.decode(digest).ok(),
+            URL_SAFE_NO_PAD.decode(digest).ok(),
+        ];
+        for bytes in attempts.into_iter().flatten() {
+            if !decoded.contains(&bytes) {
+                decoded.push(bytes);
+            }
+        }
+        decoded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("8c6d115258631625b625486f81b09532", vec![HashAlgorithm::Md5]; "32 hex is md5")]
+    #[test_case("8C6D115258631625B625486F81B09532", vec![HashAlgorithm::Md5]; "uppercase hex")]
+    #[test_case("a9993e364706816aba3e25717850c26c9cd0d89d", vec![HashAlgorithm::Sha1]; "40 hex is sha1")]
+    #[test_case("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad", vec![HashAlgorithm::Sha256, HashAlgorithm::Blake3]; "64 hex is ambiguous")]
+    #[test_case("352441c2", vec![HashAlgorithm::Crc32]; "8 hex is crc32")]
+    #[test_case("kAFQmDzST7DWlj99KOF/cg==", vec![HashAlgorithm::Md5]; "base64 md5")]
+    #[test_case("qZk-NkcGgWq6PiVxeFDCbJzQ2J0", vec![HashAlgorithm::Sha1]; "url safe base64 sha1 without padding")]
+    #[test_case("sha1:a9993e364706816aba3e25717850c26c9cd0d89d", vec![HashAlgorithm::Sha1]; "prefixed algorithm")]
+    #[test_case("$blake3$ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad", vec![HashAlgorithm::Blake3]; "crypt style prefix")]
+    fn parse_detects_candidate_algorithms(spec: &str, expected: Vec<HashAlgorithm>) {
+        assert_eq!(Password::parse(spec, None).unwrap().candidates(), expected);
+    }
+
+    #[test]
+    fn parse_uses_given_algorithm_for_ambiguous_digest() {
+        let password = Password::parse("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad", Some(HashAlgorithm::Sha256)).unwrap();
+
+        assert_eq!(password.candidates(), vec![HashAlgorithm::Sha256]);
+    }
+
+    #[test_case("not-a-digest", None; "not hex nor base64")]
+    #[test_case("abcdef", None; "unknown length")]
+    #[test_case("sha256:8c6d115258631625b625486f81b09532", None; "wrong length for prefixed algorithm")]
+    #[test_case("8c6d115258631625b625486f81b09532", Some(HashAlgorithm::Sha1); "wrong length for given algorithm")]
+    #[test_case("whirlpool:8c6d115258631625b625486f81b09532", None; "unknown algorithm")]
+    #[test_case("$sha256$8c6d115258631625b625486f81b09532$extra", None; "trailing field")]
+    fn parse_rejects_invalid_digest(spec: &str, algorithm: Option<HashAlgorithm>) {
+        assert!(Password::parse(spec, algorithm).is_err());
+    }
+
+    #[test_case("$1$saltsalt$qjXMvbEw8oaL.CzflDugX/"; "md5 crypt")]
+    #[test_case("$5$rounds=5000$saltsalt$Gcm6FsVtF/Qa77ZKD.iwsJlCVPY0XSMgLJL0Hnww/c1"; "sha256 crypt")]
+    #[test_case("$6$saltsalt$qFmFH.bQmmtXzyBY0s9v7Oicd2z4XSIecDzlB5KiA2/jctKu9YterLp8wwnSq.qc.eoxqOmSuNp2xS0ktL3nh/"; "sha512 crypt")]
+    #[test_case("$2b$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW"; "bcrypt")]
+    fn parse_rejects_crypt_formats(spec: &str) {
+        assert!(Password::parse(spec, None).unwrap_err().contains("crypt(3) formats are not supported"));
+    }
+
+    #[test]
+    fn merge_keeps_labels_of_same_target() {
+        let mut alice = Password::parse("8c6d115258631625b625486f81b09532", None).unwrap().with_label("alice");
+        let bob = Password::parse("8C6D115258631625B625486F81B09532", None).unwrap().with_label("bob").with_label("alice");
+        let salted = Password::parse("8c6d115258631625b625486f81b09532", None).unwrap()
+            .with_template(HashTemplate { suffix: String::from("salt"), ..HashTemplate::default() });
+
+        assert!(alice.is_same_target(&bob));
+        assert!(!alice.is_same_target(&salted));
+        alice.merge(bob);
+
+        assert_eq!(alice.labels(), ["alice", "bob"]);
+    }
+
+    #[test]
+    fn mark_found_reports_matched_algorithm() {
+        let mut password = Password::parse("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad", None).unwrap();
+
+        password.mark_found("abc", vec![String::from("abc")], HashAlgorithm::Sha256, Some(Rendering::default()), None);
+
+        assert_eq!(password.phrase(), Some("abc"));
+        assert_eq!(password.algorithm(), Some(HashAlgorithm::Sha256));
+        assert_eq!(password.pattern(), vec![3]);
+    }
+}
@@ -1,95 +1,390 @@
-use std::sync::{mpsc::Receiver, Mutex, Arc}; use itertools::Itertools;
-use md5;
+use std::collections::HashMap;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use itertools::Itertools;
 
-use crate::Password;
+use crate::cancellation::CancellationToken;
+use crate::checkpoint::Progress;
+use crate::combination_finder::{expand, expand_ranked, Combination};
+use crate::dictionary::WordRanks;
+use crate::targets::Targets;
+use crate::{HashAlgorithm, HashTemplate, Password, RenderRules};
+
+/// Combination queue shared by every hashing worker.
+pub type SharedReceiver = Arc<Mutex<Receiver<Combination>>>;
+
+/// How long an idle worker waits for a combination before checking whether another worker
+/// found the last password.
+const IDLE_CHECK: Duration = Duration::from_millis(50);
 
 pub struct PermutationsFinder {
-    passwords: Arc<Mutex<Vec<Password>>>,
-    data_rx: Receiver<Vec<String>>,
+    targets: Arc<Targets>,
+    data_rx: SharedReceiver,
+    rules: RenderRules,
+    snapshot: Vec<(usize, Password)>,
+    seen: usize,
+    cancel: CancellationToken,
+    progress: Option<Arc<Progress>>,
+    ranks: Option<WordRanks>,
+    tested: u64,
 }
 
 
 impl PermutationsFinder {
-    pub fn new(passwords: Arc<Mutex<Vec<Password>>>, data_rx: Receiver<Vec<String>>) -> Self {
+    pub fn new(targets: Arc<Targets>, data_rx: SharedReceiver, rules: RenderRules) -> Self {
+        let snapshot = targets.snapshot();
+        let seen = targets.found();
         Self {
-            passwords,
+            targets,
             data_rx,
+            rules,
+            snapshot,
+            seen,
+            cancel: CancellationToken::new(),
+            progress: None,
+            ranks: None,
+            tested: 0,
         }
     }
 
+    /// Stops the worker once `cancel` is cancelled, the worker finding the last password
+    /// cancels it in turn.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Records every combination in `progress` once all of its permutations were hashed.
+    pub fn with_progress(mut self, progress: Arc<Progress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Only hashes the word multisets of a combination not tried in a lower search tier.
+    pub fn with_ranks(mut self, ranks: WordRanks) -> Self {
+        self.ranks = Some(ranks);
+        self
+    }
+
     pub fn run(&mut self) -> Vec<String> {
         info!("PermutationsFinder running...");
         let mut result = vec![];
-        loop {
-            if let Ok(combination) = self.data_rx.try_recv() {
-                if let Some(phrase) = self.find(combination) {
+        while !self.targets.is_complete() && !self.cancel.is_cancelled() {
+            let received = self.data_rx.lock().unwrap().recv_timeout(IDLE_CHECK);
+            let combination = match received {
+                Ok(combination) => combination,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            let expanded = match &self.ranks {
+                Some(ranks) => expand_ranked(&combination, ranks),
+                None => expand(&combination.classes),
+            };
+            for words in expanded {
+                for phrase in self.find(words) {
                     info!("Password found: {}", phrase);
                     result.push(phrase);
                 }
             }
-            if result.len() == self.passwords.lock().unwrap().len() { break }
+            self.targets.record_tested(std::mem::take(&mut self.tested));
+            if let Some(progress) = &self.progress {
+                progress.hashed(&combination);
+            }
+        }
+        if self.targets.is_complete() {
+            self.cancel.cancel();
         }
         info!("PermutationsFinder stopped!");
         result
     }
 
-    fn find(&mut self, combination: Vec<String>) -> Option<String> {
+    /// Hashes every permutation of `words` over and over for about `duration`,
+    /// returns the number of candidate phrases tested per second.
+    pub fn calibrate(&mut self, words: &[String], duration: Duration) -> f64 {
+        let started = Instant::now();
+        let mut tested = 0;
+        while started.elapsed() < duration {
+            self.find(words.to_vec());
+            tested += std::mem::take(&mut self.tested);
+        }
+        tested as f64 / started.elapsed().as_secs_f64()
+    }
+
+    /// Hashes every permutation of `combination` in every rendering against the targets still
+    /// missing, so targets sharing a word multiset are all found. Returns the phrases recorded,
+    /// one per target, and stops early once no target is left.
+    fn find(&mut self, combination: Vec<String>) -> Vec<String> {
+        let mut found = vec![];
+        self.refresh();
         for perm in combination.iter().permutations(combination.len()).unique() {
-            let phrase = perm.iter().copied().join(" ");
-            let digest = md5::compute(&phrase);
-            for password in &mut *self.passwords.lock().unwrap() {
-                if password.found == false && format!("{:x}", digest).eq(&password.digest) {
-                    password.phrase = phrase.clone();
-                    password.found = true;
-                    return Some(phrase)
+            for (phrase, rendering) in self.rules.render(&perm) {
+                if self.snapshot.is_empty() {
+                    return found;
+                }
+                self.tested += 1;
+                let matched = {
+                    let mut digests: HashMap<(HashAlgorithm, &HashTemplate), Vec<u8>> = HashMap::new();
+                    self.snapshot.iter()
+                        .filter_map(|(i, password)| {
+                            let template = password.template();
+                            password.digests().find(|(algorithm, expected)| {
+                                digests.entry((*algorithm, template)).or_insert_with(|| template.apply(*algorithm, &phrase)) == expected
+                            }).map(|(algorithm, _)| (*i, *algorithm))
+                        })
+                        .collect::<Vec<(usize, HashAlgorithm)>>()
+                };
+                if matched.is_empty() {
+                    continue;
                 }
+                for (i, algorithm) in matched {
+                    if self.targets.mark_found(i, &phrase, perm.iter().map(|word| word.to_string()).collect(), algorithm, rendering.clone()) {
+                        found.push(phrase.clone());
+                    }
+                }
+                self.refresh();
             }
         }
-        None
+        found
+    }
+
+    /// Drops passwords other workers found since the snapshot was taken.
+    fn refresh(&mut self) {
+        let found = self.targets.found();
+        if found != self.seen {
+            self.snapshot = self.targets.snapshot();
+            self.seen = found;
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{mpsc, Arc};
+    use std::sync::mpsc;
     use test::Bencher;
-    use md5;
 
     use super::*;
+    use crate::dictionary::AnagramClass;
+    use crate::{Casing, Rendering};
+
+    fn combination(classes: &[&[&str]]) -> Combination {
+        Combination {
+            pattern: 0,
+            rank: 0,
+            position: vec![0; classes.len()],
+            classes: classes.iter().map(|words| AnagramClass::from(words.iter().map(|word| word.to_string()).collect::<Vec<String>>())).collect(),
+        }
+    }
 
     #[test]
     fn test_find_create_expected_permutations() {
         let (_, in_rx) = mpsc::channel();
-        let passwords = Arc::new(Mutex::new(vec![Password::new(format!("{:x}", md5::compute(b"this is password"))), Password::new(format!("{:x}", md5::compute(b"not a password")))]));
-        let mut permutations_finder = PermutationsFinder::new(passwords, in_rx);
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"this is password").to_vec()), Password::new(HashAlgorithm::Md5, md5::compute(b"not a password").to_vec())]));
+        let mut permutations_finder = PermutationsFinder::new(passwords, Arc::new(Mutex::new(in_rx)), RenderRules::default());
+
+        let res = permutations_finder.find(vec!["password".to_string(), "is".to_string(), "this".to_string()]);
+
+        assert_eq!(res, vec!["this is password"]);
+    }
+
+    #[test]
+    fn test_find_matches_targets_of_mixed_algorithms() {
+        let (_, in_rx) = mpsc::channel();
+        let passwords = Arc::new(Targets::new(vec![
+            Password::new(HashAlgorithm::Sha256, HashAlgorithm::Sha256.hasher().digest(b"this is password")),
+            Password::new(HashAlgorithm::Crc32, HashAlgorithm::Crc32.hasher().digest(b"password is this")),
+        ]));
+        let mut permutations_finder = PermutationsFinder::new(passwords.clone(), Arc::new(Mutex::new(in_rx)), RenderRules::default());
+
+        let first = permutations_finder.find(vec!["password".to_string(), "is".to_string(), "this".to_string()]);
+        let second = permutations_finder.find(vec!["password".to_string(), "is".to_string(), "this".to_string()]);
+
+        assert_eq!(first, vec!["password is this", "this is password"]);
+        assert!(second.is_empty());
+        assert!(passwords.passwords().iter().all(Password::is_found));
+    }
+
+    #[test]
+    fn test_find_matches_every_target_of_one_word_multiset() {
+        let (_, in_rx) = mpsc::channel();
+        let salted = HashTemplate { suffix: String::from("pepper"), ..HashTemplate::default() };
+        let passwords = Arc::new(Targets::new(vec![
+            Password::new(HashAlgorithm::Md5, md5::compute(b"this is test").to_vec()),
+            Password::new(HashAlgorithm::Md5, md5::compute(b"test is this").to_vec()),
+            Password::new(HashAlgorithm::Md5, md5::compute(b"THIS IS TEST").to_vec()),
+            Password::new(HashAlgorithm::Md5, md5::compute(b"this is testpepper").to_vec()).with_template(salted),
+            Password::new(HashAlgorithm::Sha1, HashAlgorithm::Sha1.hasher().digest(b"this is test")),
+        ]));
+        let rules = RenderRules { casings: vec![Casing::Lower, Casing::Upper], ..RenderRules::default() };
+        let mut permutations_finder = PermutationsFinder::new(passwords.clone(), Arc::new(Mutex::new(in_rx)), rules);
+
+        let res = permutations_finder.find(vec!["is".to_string(), "test".to_string(), "this".to_string()]);
+
+        assert_eq!(res.len(), 5);
+        assert!(passwords.is_complete());
+        assert_eq!(passwords.passwords()[1].phrase(), Some("test is this"));
+        assert_eq!(passwords.passwords()[2].phrase(), Some("THIS IS TEST"));
+    }
+
+    #[test]
+    fn test_find_applies_target_template() {
+        let (_, in_rx) = mpsc::channel();
+        let template = HashTemplate { prefix: String::from("salt$"), iterations: 2, ..HashTemplate::default() };
+        let digest = template.apply(HashAlgorithm::Sha1, "this is password");
+        let passwords = Arc::new(Targets::new(vec![
+            Password::new(HashAlgorithm::Sha1, HashAlgorithm::Sha1.hasher().digest(b"salt$this is password")),
+            Password::new(HashAlgorithm::Sha1, digest).with_template(template),
+        ]));
+        let mut permutations_finder = PermutationsFinder::new(passwords.clone(), Arc::new(Mutex::new(in_rx)), RenderRules::default());
+
+        let res = permutations_finder.find(vec!["password".to_string(), "is".to_string(), "this".to_string()]);
+
+        assert_eq!(res, vec!["this is password"]);
+        assert!(!passwords.passwords()[0].is_found());
+        assert!(passwords.passwords()[1].is_found());
+    }
+
+    #[test]
+    fn test_find_reports_matched_rendering() {
+        let (_, in_rx) = mpsc::channel();
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"This-Is-Password!").to_vec())]));
+        let rules = RenderRules {
+            separators: vec![String::from(" "), String::from("-")],
+            casings: vec![Casing::Lower, Casing::Title],
+            trailing: vec![String::new(), String::from("!")],
+        };
+        let mut permutations_finder = PermutationsFinder::new(passwords.clone(), Arc::new(Mutex::new(in_rx)), rules);
 
-        let res = permutations_finder.find(vec!["password".to_string(), "is".to_string(), "this".to_string()]).unwrap();
+        let res = permutations_finder.find(vec!["password".to_string(), "is".to_string(), "this".to_string()]);
 
-        assert_eq!(res, "this is password");
+        assert_eq!(res, vec!["This-Is-Password!"]);
+        assert_eq!(passwords.passwords()[0].rendering(), Some(&Rendering {
+            separator: String::from("-"),
+            casing: Casing::Title,
+            trailing: String::from("!"),
+        }));
     }
 
     #[test]
     fn run_stops_after_all_passwords_found() {
         let (in_tx, in_rx) = mpsc::channel();
-        let passwords = Arc::new(Mutex::new(vec![Password::new(format!("{:x}", md5::compute(b"this is password"))), Password::new(format!("{:x}", md5::compute(b"yet another password")))]));
-        let mut permutations_finder = PermutationsFinder::new(passwords, in_rx);
-        let combinations = vec![
-            vec!["some".to_string(), "just".to_string(), "words".to_string()],
-            vec!["is".to_string(), "this".to_string(), "password".to_string()],
-            vec!["yet".to_string(), "password".to_string(), "another".to_string()],
-        ];
-
-        for combination in combinations {
-            let _ = in_tx.send(combination);
-        }
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"this is password").to_vec()), Password::new(HashAlgorithm::Md5, md5::compute(b"yet another password").to_vec())]));
+        let mut permutations_finder = PermutationsFinder::new(passwords, Arc::new(Mutex::new(in_rx)), RenderRules::default());
+        let _ = in_tx.send(combination(&[&["some"], &["just"], &["words"]]));
+        let _ = in_tx.send(combination(&[&["is"], &["hist", "hits", "this"], &["password"]]));
+        let _ = in_tx.send(combination(&[&["yet"], &["password"], &["another"]]));
         let _ = permutations_finder.run();
     }
 
+    #[test]
+    fn run_stops_when_combinations_run_out() {
+        let (in_tx, in_rx) = mpsc::channel();
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"this is password").to_vec())]));
+        let mut permutations_finder = PermutationsFinder::new(passwords, Arc::new(Mutex::new(in_rx)), RenderRules::default());
+
+        let _ = in_tx.send(combination(&[&["some"], &["words"]]));
+        drop(in_tx);
+
+        assert!(permutations_finder.run().is_empty());
+    }
+
+    #[test]
+    fn run_records_hashed_combinations() {
+        let (in_tx, in_rx) = mpsc::channel();
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"this is password").to_vec())]));
+        let progress = Arc::new(Progress::new(vec![vec![4, 5]], None));
+        let mut permutations_finder = PermutationsFinder::new(passwords, Arc::new(Mutex::new(in_rx)), RenderRules::default())
+            .with_progress(progress.clone());
+        let sent = combination(&[&["some"], &["words"]]);
+
+        progress.emitted(&sent);
+        progress.searched(0);
+        let _ = in_tx.send(sent);
+        drop(in_tx);
+        permutations_finder.run();
+
+        assert!(progress.is_done(0));
+    }
+
+    #[test]
+    fn run_counts_tested_candidates() {
+        let (in_tx, in_rx) = mpsc::channel();
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"this is password").to_vec())]));
+        let mut permutations_finder = PermutationsFinder::new(passwords.clone(), Arc::new(Mutex::new(in_rx)), RenderRules::default());
+
+        let _ = in_tx.send(combination(&[&["some"], &["just"], &["words"]]));
+        let _ = in_tx.send(combination(&[&["its", "sit"], &["the"]]));
+        drop(in_tx);
+        permutations_finder.run();
+
+        assert_eq!(passwords.tested(), 6 + 4);
+    }
+
+    #[test]
+    fn run_cancels_token_after_all_passwords_found() {
+        let (in_tx, in_rx) = mpsc::channel();
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"this is password").to_vec())]));
+        let cancel = CancellationToken::new();
+        let mut permutations_finder = PermutationsFinder::new(passwords, Arc::new(Mutex::new(in_rx)), RenderRules::default())
+            .with_cancellation(cancel.clone());
+
+        let _ = in_tx.send(combination(&[&["is"], &["password"], &["this"]]));
+
+        assert_eq!(permutations_finder.run(), vec!["this is password"]);
+        assert!(cancel.is_cancelled());
+    }
+
+    #[test]
+    fn run_stops_when_cancelled() {
+        let (_in_tx, in_rx) = mpsc::channel();
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"this is password").to_vec())]));
+        let cancel = CancellationToken::new();
+        let mut permutations_finder = PermutationsFinder::new(passwords, Arc::new(Mutex::new(in_rx)), RenderRules::default())
+            .with_cancellation(cancel.clone());
+
+        cancel.cancel();
+
+        assert!(permutations_finder.run().is_empty());
+    }
+
+    #[test]
+    fn workers_sharing_queue_find_each_password_once() {
+        let (in_tx, in_rx) = mpsc::channel();
+        let in_rx = Arc::new(Mutex::new(in_rx));
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"this is password").to_vec()), Password::new(HashAlgorithm::Md5, md5::compute(b"yet another password").to_vec())]));
+        for _ in 0..4 {
+            let _ = in_tx.send(combination(&[&["is"], &["password"], &["this"]]));
+        }
+        let _ = in_tx.send(combination(&[&["another"], &["password"], &["yet"]]));
+        drop(in_tx);
+
+        let workers = (0..3).map(|_| {
+            let mut permutations_finder = PermutationsFinder::new(passwords.clone(), in_rx.clone(), RenderRules::default());
+            std::thread::spawn(move || permutations_finder.run())
+        }).collect::<Vec<_>>();
+        let found = workers.into_iter().flat_map(|worker| worker.join().unwrap()).sorted().collect::<Vec<String>>();
+
+        assert_eq!(found, vec!["this is password", "yet another password"]);
+        assert!(passwords.is_complete());
+    }
+
+    #[test]
+    fn calibrate_measures_tested_candidates() {
+        let (_, in_rx) = mpsc::channel();
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"not a password").to_vec())]));
+        let mut permutations_finder = PermutationsFinder::new(passwords.clone(), Arc::new(Mutex::new(in_rx)), RenderRules::default());
+
+        let rate = permutations_finder.calibrate(&["is".to_string(), "password".to_string(), "this".to_string()], Duration::from_millis(20));
+
+        assert!(rate > 0.0);
+        assert_eq!(passwords.tested(), 0);
+    }
+
     #[bench]
     fn bench_find(b: &mut Bencher) {
         let (_, in_rx) = mpsc::channel();
-        let passwords = Arc::new(Mutex::new(vec![Password::new(format!("{:x}", md5::compute(b"this is password"))), Password::new(format!("{:x}", md5::compute(b"not a password")))]));
-        let mut permutations_finder = PermutationsFinder::new(passwords, in_rx);
+        let passwords = Arc::new(Targets::new(vec![Password::new(HashAlgorithm::Md5, md5::compute(b"this is password").to_vec()), Password::new(HashAlgorithm::Md5, md5::compute(b"not a password").to_vec())]));
+        let mut permutations_finder = PermutationsFinder::new(passwords, Arc::new(Mutex::new(in_rx)), RenderRules::default());
 
         b.iter(|| {
             (1..1000).for_each(|_| {
@@ -0,0 +1,92 @@
+use crate::normalizer::Normalizer;
+
+pub struct Phrase {
+    text: String,
+    letters: Vec<char>,
+}
+
+impl Phrase {
+    pub fn new(text: &str) -> Self {
+        Phrase::with_normalizer(text, &Normalizer::default())
+    }
+
+    /// Letters of `text` as `normalizer` sees them, so they compare with dictionary words normalized alike.
+    pub fn with_normalizer(text: &str, normalizer: &Normalizer) -> Self {
+        let mut letters = normalizer.letters(text);
+        letters.sort();
+        Phrase {
+            text: text.to_string(),
+            letters,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn letters(&self) -> &[char] {
+        &self.letters
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    pub fn target_length(&self, requested: Option<usize>) -> Result<usize, String> {
+        match requested {
+            Some(length) if length != self.len() => Err(format!(
+                "target length {} does not match the {} letters of phrase '{}'",
+                length, self.len(), self.text
+            )),
+            _ => Ok(self.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("poultry outwits ants", 18; "spaces")]
+    #[test_case("Poultry, Outwits & Ants!", 18; "punctuation and mixed case")]
+    #[test_case("  sitt\tthesis\n", 10; "surrounding whitespace")]
+    #[test_case("?! -", 0; "no letters")]
+    fn len_counts_only_letters(text: &str, expected: usize) {
+        assert_eq!(Phrase::new(text).len(), expected);
+    }
+
+    #[test]
+    fn letters_are_lowercase_and_sorted() {
+        let phrase = Phrase::new("It's, Sit!");
+
+        assert_eq!(phrase.letters(), &['i', 'i', 's', 's', 't', 't']);
+    }
+
+    #[test_case("Crème brûlée", false, 11; "accents kept")]
+    #[test_case("Crème brûlée", true, 11; "accents stripped")]
+    #[test_case("cre\u{300}me", false, 5; "combining accent")]
+    fn len_counts_graphemes(text: &str, strip_accents: bool, expected: usize) {
+        assert_eq!(Phrase::with_normalizer(text, &Normalizer::new().with_accent_stripping(strip_accents)).len(), expected);
+    }
+
+    #[test]
+    fn letters_without_accents_when_stripped() {
+        let phrase = Phrase::with_normalizer("Éte", &Normalizer::new().with_accent_stripping(true));
+
+        assert_eq!(phrase.letters(), &['e', 'e', 't']);
+    }
+
+    #[test_case(None, Ok(10); "derived")]
+    #[test_case(Some(10), Ok(10); "consistent override")]
+    #[test_case(Some(12), Err(()); "inconsistent override")]
+    fn target_length_returns_expected(requested: Option<usize>, expected: Result<usize, ()>) {
+        let phrase = Phrase::new("Sitt thesis.");
+
+        assert_eq!(phrase.target_length(requested).map_err(|_| ()), expected);
+    }
+}
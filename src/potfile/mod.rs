@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::hasher::HashAlgorithm;
+use crate::password::Password;
+
+/// Cracked digest as stored in a potfile line, `algorithm:hexdigest:phrase`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PotEntry {
+    pub algorithm: HashAlgorithm,
+    pub digest: Vec<u8>,
+    pub phrase: String,
+}
+
+impl PotEntry {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut fields = line.splitn(3, ':');
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(algorithm), Some(digest), Some(phrase)) => Ok(PotEntry {
+                algorithm: algorithm.parse()?,
+                digest: hex::decode(digest).map_err(|e| format!("invalid digest '{}': {}", digest, e))?,
+                phrase: phrase.to_string(),
+            }),
+            _ => Err(format!("expected algorithm:digest:phrase, got '{}'", line)),
+        }
+    }
+}
+
+impl fmt::Display for PotEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.algorithm, hex::encode(&self.digest), self.phrase)
+    }
+}
+
+/// Append-only record of every password cracked so far, shared by runs so targets are only searched once.
+#[derive(Debug)]
+pub struct Potfile {
+    path: PathBuf,
+    entries: Mutex<HashMap<(HashAlgorithm, Vec<u8>), String>>,
+}
+
+impl Potfile {
+    /// Reads the potfile at `path`, a missing file is an empty potfile. Malformed lines are skipped.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut entries = HashMap::new();
+        match File::open(path) {
+            Ok(file) => for (number, line) in BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.is_empty() {
+                    continue;
+                }
+                match PotEntry::parse(&line) {
+                    Ok(entry) => {
+                        entries.insert((entry.algorithm, entry.digest), entry.phrase);
+                    }
+                    Err(e) => warn!("Skipping line {} of potfile {}: {}", number + 1, path.display(), e),
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(Potfile {
+            path: path.to_path_buf(),
+            entries: Mutex::new(entries),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Phrase cracked for any algorithm the digest of `password` may belong to.
+    pub fn lookup(&self, password: &Password) -> Option<PotEntry> {
+        let entries = self.entries.lock().unwrap();
+        password.digests().find_map(|(algorithm, digest)| {
+            entries.get(&(*algorithm, digest.clone())).map(|phrase| PotEntry {
+                algorithm: *algorithm,
+                digest: digest.clone(),
+                phrase: phrase.clone(),
+            })
+        })
+    }
+
+    /// Marks the passwords cracked before, returns how many were.
+    pub fn restore(&self, passwords: &mut [Password]) -> usize {
+        let mut restored = 0;
+        for password in passwords.iter_mut().filter(|password| !password.is_found()) {
+            if let Some(entry) = self.lookup(password) {
+                password.mark_found(&entry.phrase, vec![], entry.algorithm, None, None);
+                restored += 1;
+            }
+        }
+        restored
+    }
+
+    /// Appends the match of a found password, unless the potfile already knows it.
+    pub fn record(&self, password: &Password) -> io::Result<()> {
+        let (Some(algorithm), Some(phrase)) = (password.algorithm(), password.phrase()) else {
+            return Ok(());
+        };
+        let Some((_, digest)) = password.digests().find(|(candidate, _)| *candidate == algorithm) else {
+            return Ok(());
+        };
+        let mut entries = self.entries.lock().unwrap();
+        if entries.contains_key(&(algorithm, digest.clone())) {
+            return Ok(());
+        }
+        let entry = PotEntry { algorithm, digest: digest.clone(), phrase: phrase.to_string() };
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", entry)?;
+        entries.insert((entry.algorithm, entry.digest), entry.phrase);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::Rendering;
+    use test_case::test_case;
+
+    fn potfile_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("anagram-potfile-{}-{}.pot", name, std::process::id()))
+    }
+
+    #[test_case("md5:8c6d115258631625b625486f81b09532:this is test", "this is test"; "plain phrase")]
+    #[test_case("md5:8c6d115258631625b625486f81b09532:this:is:test", "this:is:test"; "phrase with colons")]
+    fn parse_keeps_phrase_after_digest(line: &str, phrase: &str) {
+        let entry = PotEntry::parse(line).unwrap();
+
+        assert_eq!((entry.algorithm, entry.phrase.as_str()), (HashAlgorithm::Md5, phrase));
+        assert_eq!(entry.to_string(), line);
+    }
+
+    #[test_case("8c6d115258631625b625486f81b09532"; "missing fields")]
+    #[test_case("whirlpool:8c6d115258631625b625486f81b09532:this is test"; "unknown algorithm")]
+    #[test_case("md5:not-hex:this is test"; "malformed digest")]
+    fn parse_rejects_malformed_line(line: &str) {
+        assert!(PotEntry::parse(line).is_err());
+    }
+
+    #[test]
+    fn record_and_restore_round_trip() {
+        let path = potfile_path("round-trip");
+        let mut found = Password::parse("8C6D115258631625B625486F81B09532", None).unwrap();
+        found.mark_found("this is test", vec![], HashAlgorithm::Md5, Some(Rendering::default()), None);
+        let potfile = Potfile::load(&path).unwrap();
+        potfile.record(&found).unwrap();
+        potfile.record(&found).unwrap();
+
+        let mut passwords = vec![
+            Password::parse("md5:8c6d115258631625b625486f81b09532", None).unwrap(),
+            Password::parse("d1e744eced74494c7a5292a93252eb6c", None).unwrap(),
+        ];
+        let reloaded = Potfile::load(&path).unwrap();
+        let restored = reloaded.restore(&mut passwords);
+        let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(lines, 1);
+        assert_eq!(restored, 1);
+        assert_eq!(passwords[0].phrase(), Some("this is test"));
+        assert_eq!(passwords[0].algorithm(), Some(HashAlgorithm::Md5));
+        assert!(!passwords[1].is_found());
+    }
+
+    #[test]
+    fn load_treats_missing_file_as_empty() {
+        assert!(Potfile::load(&potfile_path("missing")).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::events::secs;
+use crate::targets::Targets;
+
+/// Counters the finders update while searching, read periodically to report progress.
+/// `sizes` holds the number of word tuples of every length pattern, so the share of the
+/// search space behind the finders can be estimated from their positions.
+#[derive(Debug)]
+pub struct Meter {
+    started: Instant,
+    sizes: Vec<f64>,
+    searched: Vec<AtomicU64>,
+    patterns_done: AtomicUsize,
+    tuples: AtomicU64,
+}
+
+impl Meter {
+    pub fn new(sizes: Vec<f64>) -> Self {
+        let searched = sizes.iter().map(|_| AtomicU64::new(0f64.to_bits())).collect();
+        Meter {
+            started: Instant::now(),
+            sizes,
+            searched,
+            patterns_done: AtomicUsize::new(0),
+            tuples: AtomicU64::new(0),
+        }
+    }
+
+    /// Records the share of `pattern` searched so far.
+    pub fn advance(&self, pattern: usize, searched: f64) {
+        self.searched[pattern].store(searched.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn finish(&self, pattern: usize) {
+        self.advance(pattern, 1.0);
+        self.patterns_done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Adds `count` tuples the finders tested against the letters of the phrase.
+    pub fn tuples_tested(&self, count: u64) {
+        self.tuples.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self, targets: Option<&Targets>) -> Snapshot {
+        let total = self.sizes.iter().sum::<f64>();
+        let searched = self.sizes.iter().zip(&self.searched)
+            .map(|(size, searched)| size * f64::from_bits(searched.load(Ordering::Relaxed)))
+            .sum::<f64>();
+        Snapshot {
+            elapsed: self.started.elapsed(),
+            patterns: self.sizes.len(),
+            patterns_done: self.patterns_done.load(Ordering::Relaxed),
+            searched: if total > 0.0 { searched / total } else { 1.0 },
+            tuples: self.tuples.load(Ordering::Relaxed),
+            candidates: targets.map_or(0, Targets::tested),
+            found: targets.map_or(0, Targets::found),
+            targets: targets.map_or(0, Targets::len),
+        }
+    }
+}
+
+/// Progress of a run at one point in time.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Snapshot {
+    #[serde(rename = "elapsed_secs", serialize_with = "secs")]
+    pub elapsed: Duration,
+    pub patterns: usize,
+    pub patterns_done: usize,
+    /// Estimated share of the search space behind the finders, between 0 and 1.
+    pub searched: f64,
+    /// Tuples of anagram classes the finders tested, partial ones included.
+    pub tuples: u64,
+    pub candidates: u64,
+    pub found: usize,
+    pub targets: usize,
+}
+
+impl Snapshot {
+    pub fn tuples_per_sec(&self) -> f64 {
+        rate(self.tuples, self.elapsed)
+    }
+
+    pub fn candidates_per_sec(&self) -> f64 {
+        rate(self.candidates, self.elapsed)
+    }
+
+    /// Time left at the average pace so far, unknown until some of the space is searched.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.searched <= 0.0 {
+            return None;
+        }
+        let left = self.elapsed.as_secs_f64() * (1.0 - self.searched) / self.searched;
+        Some(Duration::from_secs_f64(left.max(0.0)))
+    }
+
+    /// Single line with a bar, meant to be redrawn in place on a terminal.
+    pub fn bar(&self, width: usize) -> String {
+        let filled = ((self.searched.clamp(0.0, 1.0) * width as f64) as usize).min(width);
+        format!(
+            "[{}{}] {:.1}% {} patterns {}/{} found {}/{} {}/s ETA {}",
+            "#".repeat(filled), ".".repeat(width - filled), self.searched * 100.0,
+            format_duration(self.elapsed), self.patterns_done, self.patterns,
+            self.found, self.targets, format_count(self.candidates_per_sec()), self.eta().map_or(String::from("?"), format_duration)
+        )
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "elapsed {}, patterns {}/{}, tuples {} ({}/s), candidates {} ({}/s), found {}/{}, searched {:.1}%, ETA {}",
+            format_duration(self.elapsed), self.patterns_done, self.patterns,
+            format_count(self.tuples as f64), format_count(self.tuples_per_sec()),
+            format_count(self.candidates as f64), format_count(self.candidates_per_sec()),
+            self.found, self.targets, self.searched * 100.0, self.eta().map_or(String::from("?"), format_duration)
+        )
+    }
+}
+
+/// How progress is shown while a run is going on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// Bar on a terminal, nothing otherwise.
+    Auto,
+    Bar,
+    Lines,
+    Off,
+}
+
+impl ProgressMode {
+    pub const ALL: [ProgressMode; 4] = [ProgressMode::Auto, ProgressMode::Bar, ProgressMode::Lines, ProgressMode::Off];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProgressMode::Auto => "auto",
+            ProgressMode::Bar => "bar",
+            ProgressMode::Lines => "lines",
+            ProgressMode::Off => "off",
+        }
+    }
+}
+
+impl fmt::Display for ProgressMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ProgressMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        ProgressMode::ALL.iter()
+            .find(|mode| mode.name() == name)
+            .copied()
+            .ok_or_else(|| format!("unknown progress mode '{}', expected one of: {}", s, ProgressMode::ALL.map(|m| m.name()).join(", ")))
+    }
+}
+
+fn rate(count: u64, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() { 0.0 } else { count as f64 / elapsed.as_secs_f64() }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
+}
+
+pub fn format_count(count: f64) -> String {
+    match count {
+        count if count < 1e4 => format!("{:.0}", count),
+        count if count < 1e6 => format!("{:.1}k", count / 1e3),
+        count if count < 1e9 => format!("{:.1}M", count / 1e6),
+        count if count < 1e12 => format!("{:.1}G", count / 1e9),
+        count if count < 1e15 => format!("{:.1}T", count / 1e12),
+        count => format!("{:.1e}", count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn snapshot_weights_patterns_by_size() {
+        let meter = Meter::new(vec![10.0, 30.0]);
+
+        meter.finish(0);
+        meter.advance(1, 0.5);
+
+        let snapshot = meter.snapshot(None);
+        assert_eq!(snapshot.patterns_done, 1);
+        assert_eq!(snapshot.searched, 0.625);
+    }
+
+    #[test]
+    fn eta_extrapolates_average_pace() {
+        let snapshot = Snapshot {
+            elapsed: Duration::from_secs(60),
+            patterns: 4,
+            patterns_done: 1,
+            searched: 0.25,
+            tuples: 0,
+            candidates: 0,
+            found: 0,
+            targets: 1,
+        };
+
+        assert_eq!(snapshot.eta(), Some(Duration::from_secs(180)));
+        assert_eq!(Snapshot { searched: 0.0, ..snapshot }.eta(), None);
+    }
+
+    #[test]
+    fn bar_fills_searched_share() {
+        let meter = Meter::new(vec![1.0]);
+        meter.advance(0, 0.5);
+
+        assert!(meter.snapshot(None).bar(10).starts_with("[#####.....] 50.0%"));
+    }
+
+    #[test_case(Duration::from_secs(42), "42s")]
+    #[test_case(Duration::from_secs(125), "2m05s")]
+    #[test_case(Duration::from_secs(3725), "1h02m05s")]
+    fn format_duration_returns_expected(duration: Duration, expected: &str) {
+        assert_eq!(format_duration(duration), expected);
+    }
+
+    #[test_case(999.0, "999")]
+    #[test_case(45_600.0, "45.6k")]
+    #[test_case(1_218_200.0, "1.2M")]
+    #[test_case(12_300_000_000.0, "12.3G")]
+    #[test_case(4.2e18, "4.2e18")]
+    fn format_count_returns_expected(count: f64, expected: &str) {
+        assert_eq!(format_count(count), expected);
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Casing {
+    Lower,
+    Upper,
+    Title,
+    Camel,
+}
+
+impl Casing {
+    pub const ALL: [Casing; 4] = [Casing::Lower, Casing::Upper, Casing::Title, Casing::Camel];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Casing::Lower => "lower",
+            Casing::Upper => "upper",
+            Casing::Title => "title",
+            Casing::Camel => "camel",
+        }
+    }
+
+    fn apply(&self, word: &str, position: usize) -> String {
+        match self {
+            Casing::Lower => word.to_lowercase(),
+            Casing::Upper => word.to_uppercase(),
+            Casing::Title => capitalize(word),
+            Casing::Camel if position == 0 => word.to_lowercase(),
+            Casing::Camel => capitalize(word),
+        }
+    }
+}
+
+impl fmt::Display for Casing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Casing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        Casing::ALL.iter()
+            .find(|casing| casing.name() == name)
+            .copied()
+            .ok_or_else(|| format!("unknown casing '{}', expected one of: {}", s, Casing::ALL.map(|c| c.name()).join(", ")))
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new(),
+    }
+}
+
+/// The way a matched phrase was written out before hashing.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rendering {
+    pub separator: String,
+    pub casing: Casing,
+    pub trailing: String,
+}
+
+impl Default for Rendering {
+    fn default() -> Self {
+        Rendering {
+            separator: String::from(" "),
+            casing: Casing::Lower,
+            trailing: String::new(),
+        }
+    }
+}
+
+impl fmt::Display for Rendering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "separator={:?} case={} trailing={:?}", self.separator, self.casing, self.trailing)
+    }
+}
+
+/// Every combination of separator, casing and trailing text a permutation is tried with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderRules {
+    pub separators: Vec<String>,
+    pub casings: Vec<Casing>,
+    pub trailing: Vec<String>,
+}
+
+impl Default for RenderRules {
+    fn default() -> Self {
+        RenderRules {
+            separators: vec![String::from(" ")],
+            casings: vec![Casing::Lower],
+            trailing: vec![String::new()],
+        }
+    }
+}
+
+impl RenderRules {
+    pub fn len(&self) -> usize {
+        self.separators.len() * self.casings.len() * self.trailing.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn render(&self, words: &[&String]) -> Vec<(String, Rendering)> {
+        let mut phrases = Vec::with_capacity(self.len());
+        for casing in &self.casings {
+            let cased = words.iter()
+                .enumerate()
+                .map(|(position, word)| casing.apply(word, position))
+                .collect::<Vec<String>>();
+            for separator in &self.separators {
+                let joined = cased.join(separator);
+                for trailing in &self.trailing {
+                    let rendering = Rendering {
+                        separator: separator.clone(),
+                        casing: *casing,
+                        trailing: trailing.clone(),
+                    };
+                    phrases.push((format!("{}{}", joined, trailing), rendering));
+                }
+            }
+        }
+        phrases
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(Casing::Lower, " ", "this is test"; "lower with spaces")]
+    #[test_case(Casing::Upper, "_", "THIS_IS_TEST"; "upper with underscores")]
+    #[test_case(Casing::Title, " ", "This Is Test"; "title with spaces")]
+    #[test_case(Casing::Title, "", "ThisIsTest"; "title without separator")]
+    #[test_case(Casing::Camel, "", "thisIsTest"; "camel without separator")]
+    #[test_case(Casing::Lower, "-", "this-is-test"; "lower with hyphens")]
+    fn render_applies_casing_and_separator(casing: Casing, separator: &str, expected: &str) {
+        let rules = RenderRules {
+            separators: vec![separator.to_string()],
+            casings: vec![casing],
+            trailing: vec![String::new()],
+        };
+        let words = ["this".to_string(), "is".to_string(), "test".to_string()];
+
+        let phrases = rules.render(&words.iter().collect::<Vec<&String>>());
+
+        assert_eq!(phrases.len(), 1);
+        assert_eq!(phrases[0].0, expected);
+    }
+
+    #[test]
+    fn render_expands_every_rule_combination() {
+        let rules = RenderRules {
+            separators: vec![String::from(" "), String::new()],
+            casings: vec![Casing::Lower, Casing::Upper],
+            trailing: vec![String::new(), String::from("!")],
+        };
+        let words = ["sit".to_string(), "the".to_string()];
+
+        let phrases = rules.render(&words.iter().collect::<Vec<&String>>())
+            .into_iter()
+            .map(|(phrase, _)| phrase)
+            .collect::<Vec<String>>();
+
+        assert_eq!(phrases, vec!["sit the", "sit the!", "sitthe", "sitthe!", "SIT THE", "SIT THE!", "SITTHE", "SITTHE!"]);
+    }
+
+    #[test]
+    fn default_rules_render_lowercase_words_joined_with_space() {
+        let words = ["Sit".to_string(), "the".to_string()];
+
+        let phrases = RenderRules::default().render(&words.iter().collect::<Vec<&String>>());
+
+        assert_eq!(phrases, vec![(String::from("sit the"), Rendering::default())]);
+    }
+
+    #[test_case("Title", Ok(Casing::Title))]
+    #[test_case("camel", Ok(Casing::Camel))]
+    #[test_case("snake", Err(()))]
+    fn casing_from_str_returns_expected(name: &str, expected: Result<Casing, ()>) {
+        assert_eq!(name.parse::<Casing>().map_err(|_| ()), expected);
+    }
+}
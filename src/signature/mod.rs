@@ -0,0 +1,95 @@
+/// Distinct letters of the anagram pool, each one owning a slot of a `Signature`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alphabet {
+    letters: Vec<char>,
+}
+
+impl Alphabet {
+    pub fn new(pool: &[char]) -> Self {
+        let mut letters = pool.to_vec();
+        letters.sort();
+        letters.dedup();
+        Alphabet {
+            letters,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    /// Counts letters of `word`, or returns `None` when it uses a letter outside the alphabet.
+    pub fn signature<I: IntoIterator<Item = char>>(&self, word: I) -> Option<Signature> {
+        let mut counts = vec![0; self.letters.len()];
+        for c in word {
+            let slot = self.letters.binary_search(&c).ok()?;
+            counts[slot] += 1;
+        }
+        Some(Signature(counts))
+    }
+}
+
+/// Letter frequencies of a word, two words are anagrams when their signatures are equal.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Signature(Vec<u16>);
+
+impl Signature {
+    pub fn letters(&self) -> usize {
+        self.0.iter().map(|count| *count as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|count| *count == 0)
+    }
+
+    /// Removes the letters of `other`, or returns `None` when they are not all available.
+    pub fn checked_sub(&self, other: &Signature) -> Option<Signature> {
+        self.0.iter()
+            .zip(&other.0)
+            .map(|(available, needed)| available.checked_sub(*needed))
+            .collect::<Option<Vec<u16>>>()
+            .map(Signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn alphabet() -> Alphabet {
+        Alphabet::new(&"sitt thesis".chars().filter(|c| c.is_alphabetic()).collect::<Vec<char>>())
+    }
+
+    #[test]
+    fn alphabet_keeps_distinct_letters() {
+        assert_eq!(alphabet().len(), 5);
+    }
+
+    #[test_case("sits", "tiss", true; "anagrams")]
+    #[test_case("sits", "sit", false; "different length")]
+    #[test_case("this", "hits", true; "same letters")]
+    fn signature_equal_for_anagrams(first: &str, second: &str, expected: bool) {
+        let alphabet = alphabet();
+
+        assert_eq!(alphabet.signature(first.chars()) == alphabet.signature(second.chars()), expected);
+    }
+
+    #[test]
+    fn signature_rejects_letters_outside_alphabet() {
+        assert_eq!(alphabet().signature("best".chars()), None);
+    }
+
+    #[test_case("sits", Some("the"); "fits")]
+    #[test_case("sitss", None; "too many letters")]
+    fn checked_sub_removes_letters(word: &str, expected: Option<&str>) {
+        let alphabet = alphabet();
+        let pool = alphabet.signature("sitsthe".chars()).unwrap();
+
+        assert_eq!(pool.checked_sub(&alphabet.signature(word.chars()).unwrap()), expected.and_then(|rest| alphabet.signature(rest.chars())));
+    }
+}
@@ -0,0 +1,1323 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use itertools::Itertools;
+use serde::Serialize;
+use threadpool::ThreadPool;
+
+use crate::cancellation::CancellationToken;
+use crate::checkpoint::{Checkpoint, Progress};
+use crate::cleaner::CleanRules;
+use crate::combination_finder::{expand_ranked, Combination, CombinationFinder};
+use crate::combination_generator::CombinationGenerator;
+use crate::dictionary::{AnagramClass, Dictionary, DictionarySource};
+use crate::estimate::{Estimate, PatternEstimate};
+use crate::events::{Event, EventSink, Found};
+use crate::permutations_finder::PermutationsFinder;
+use crate::normalizer::Normalizer;
+use crate::phrase::Phrase;
+use crate::potfile::Potfile;
+use crate::target_list::TargetSpec;
+use crate::progress::{Meter, Snapshot};
+use crate::targets::Targets;
+use crate::{HashAlgorithm, HashTemplate, Password, RenderRules};
+
+/// Combinations each worker may queue ahead of the hashing stage.
+const COMBINATIONS_PER_WORKER: usize = 64;
+
+/// Callback receiving the progress of a run.
+pub type ProgressReport = Arc<dyn Fn(&Snapshot) + Send + Sync>;
+
+#[derive(Debug)]
+pub enum SolverError {
+    InvalidConfig(String),
+    Dictionary(PathBuf, io::Error),
+    Output(io::Error),
+    Checkpoint(PathBuf, String),
+    Potfile(PathBuf, io::Error),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::InvalidConfig(message) => write!(f, "{}", message),
+            SolverError::Dictionary(path, e) => write!(f, "cannot load dictionary {}: {}", path.display(), e),
+            SolverError::Output(e) => write!(f, "cannot write output: {}", e),
+            SolverError::Checkpoint(path, message) => write!(f, "cannot use checkpoint {}: {}", path.display(), message),
+            SolverError::Potfile(path, e) => write!(f, "cannot read potfile {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+pub struct SolverBuilder {
+    dictionaries: Vec<DictionarySource>,
+    phrase: Option<String>,
+    target_length: Option<usize>,
+    min_word_len: usize,
+    max_word_len: usize,
+    max_words: usize,
+    max_workers: usize,
+    hash_workers: usize,
+    algorithm: Option<HashAlgorithm>,
+    template: HashTemplate,
+    targets: Vec<(TargetSpec, Option<HashTemplate>)>,
+    render_rules: RenderRules,
+    normalizer: Normalizer,
+    clean_rules: CleanRules,
+    cancel: CancellationToken,
+    checkpoint: Option<PathBuf>,
+    checkpoint_interval: Duration,
+    resume: bool,
+    potfile: Option<PathBuf>,
+    progress: Option<(Duration, ProgressReport)>,
+    events: Option<EventSink>,
+}
+
+impl Default for SolverBuilder {
+    fn default() -> Self {
+        SolverBuilder {
+            dictionaries: vec![DictionarySource::new("wordlist")],
+            phrase: None,
+            target_length: None,
+            min_word_len: 2,
+            max_word_len: 10,
+            max_words: 4,
+            max_workers: 6,
+            hash_workers: 2,
+            algorithm: None,
+            template: HashTemplate::default(),
+            targets: vec![],
+            render_rules: RenderRules::default(),
+            normalizer: Normalizer::default(),
+            clean_rules: CleanRules::default(),
+            cancel: CancellationToken::new(),
+            checkpoint: None,
+            checkpoint_interval: Duration::from_secs(60),
+            resume: false,
+            potfile: None,
+            progress: None,
+            events: None,
+        }
+    }
+}
+
+impl SolverBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the words of the single word list at `path`, a file or a directory of files.
+    pub fn dictionary<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.dictionaries = vec![DictionarySource::new(path)];
+        self
+    }
+
+    /// Merges the words of several word lists, searching those of the best ranked sources first.
+    pub fn dictionaries<I: IntoIterator<Item = DictionarySource>>(mut self, sources: I) -> Self {
+        self.dictionaries = sources.into_iter().collect();
+        self
+    }
+
+    pub fn phrase(mut self, phrase: &str) -> Self {
+        self.phrase = Some(phrase.to_string());
+        self
+    }
+
+    pub fn target_length(mut self, target_length: usize) -> Self {
+        self.target_length = Some(target_length);
+        self
+    }
+
+    pub fn min_word_len(mut self, min_word_len: usize) -> Self {
+        self.min_word_len = min_word_len;
+        self
+    }
+
+    pub fn max_word_len(mut self, max_word_len: usize) -> Self {
+        self.max_word_len = max_word_len;
+        self
+    }
+
+    pub fn max_words(mut self, max_words: usize) -> Self {
+        self.max_words = max_words;
+        self
+    }
+
+    pub fn max_workers(mut self, max_workers: usize) -> Self {
+        self.max_workers = max_workers;
+        self
+    }
+
+    /// Number of threads hashing permutations, independent of the combination finders.
+    pub fn hash_workers(mut self, hash_workers: usize) -> Self {
+        self.hash_workers = hash_workers;
+        self
+    }
+
+    /// Algorithm used for target digests that do not name one explicitly,
+    /// without it the algorithm is detected from the digest format.
+    pub fn algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.algorithm = Some(algorithm);
+        self
+    }
+
+    /// Template used for targets added without one of their own.
+    pub fn template(mut self, template: HashTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    pub fn target(mut self, digest: &str) -> Self {
+        self.targets.push((TargetSpec { digest: digest.to_string(), ..TargetSpec::default() }, None));
+        self
+    }
+
+    pub fn target_with_template(mut self, digest: &str, template: HashTemplate) -> Self {
+        self.targets.push((TargetSpec { digest: digest.to_string(), ..TargetSpec::default() }, Some(template)));
+        self
+    }
+
+    /// Targets read from a digest list. A salt is appended after the suffix of the template,
+    /// identical digests hashed the same way are searched once and keep every label.
+    pub fn target_specs<I>(mut self, specs: I) -> Self
+    where
+        I: IntoIterator<Item = TargetSpec>,
+    {
+        self.targets.extend(specs.into_iter().map(|spec| (spec, None)));
+        self
+    }
+
+    pub fn targets<I, S>(mut self, digests: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.targets.extend(digests.into_iter().map(|digest| (TargetSpec { digest: digest.as_ref().to_string(), ..TargetSpec::default() }, None)));
+        self
+    }
+
+    /// Token stopping every run of the solver once cancelled.
+    pub fn cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// File the progress of `solve` is written to every `checkpoint_interval` and when it stops.
+    pub fn checkpoint<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.checkpoint = Some(path.into());
+        self
+    }
+
+    pub fn checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    /// Continues from the checkpoint file instead of starting over.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Skips targets cracked by earlier runs and appends the ones `solve` finds.
+    pub fn potfile<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.potfile = Some(path.into());
+        self
+    }
+
+    /// Calls `report` with the progress of `solve` every `interval` and once more when it stops.
+    pub fn progress<F>(mut self, interval: Duration, report: F) -> Self
+    where
+        F: Fn(&Snapshot) + Send + Sync + 'static,
+    {
+        self.progress = Some((interval, Arc::new(report)));
+        self
+    }
+
+    /// Calls `events` for every pattern started and finished and every password found,
+    /// with the stats of every progress report and of the end of `solve`.
+    pub fn events<F>(mut self, events: F) -> Self
+    where
+        F: Fn(&Event) + Send + Sync + 'static,
+    {
+        self.events = Some(Arc::new(events));
+        self
+    }
+
+    /// Separators, casings and trailing text every permutation is written out with before hashing.
+    pub fn render_rules(mut self, render_rules: RenderRules) -> Self {
+        self.render_rules = render_rules;
+        self
+    }
+
+    /// Lets accented letters stand for their base letter, in the phrase and in dictionary words alike.
+    pub fn strip_accents(mut self, strip_accents: bool) -> Self {
+        self.normalizer = self.normalizer.with_accent_stripping(strip_accents);
+        self
+    }
+
+    /// Steps every dictionary line goes through before it is checked against the phrase.
+    pub fn clean_rules(mut self, clean_rules: CleanRules) -> Self {
+        self.clean_rules = clean_rules;
+        self
+    }
+
+    pub fn build(self) -> Result<Solver, SolverError> {
+        let invalid = |message: String| Err(SolverError::InvalidConfig(message));
+        if self.min_word_len == 0 {
+            return invalid(String::from("minimum word length must be greater than 0"));
+        }
+        if self.min_word_len > self.max_word_len {
+            return invalid(format!("minimum word length ({}) cannot be greater than maximum word length ({})", self.min_word_len, self.max_word_len));
+        }
+        if self.dictionaries.is_empty() {
+            return invalid(String::from("at least one dictionary is needed"));
+        }
+        if self.max_words == 0 {
+            return invalid(String::from("maximum number of words must be greater than 0"));
+        }
+        if self.max_workers == 0 {
+            return invalid(String::from("number of workers must be greater than 0"));
+        }
+        if self.hash_workers == 0 {
+            return invalid(String::from("number of hash workers must be greater than 0"));
+        }
+        let phrase = match &self.phrase {
+            Some(phrase) => Phrase::with_normalizer(phrase, &self.normalizer),
+            None => return invalid(String::from("no phrase given")),
+        };
+        if phrase.is_empty() {
+            return invalid(String::from("phrase must contain at least one letter"));
+        }
+        let target_length = phrase.target_length(self.target_length).map_err(SolverError::InvalidConfig)?;
+        if target_length < self.min_word_len {
+            return invalid(format!("target length {} is shorter than minimum word length ({})", target_length, self.min_word_len));
+        }
+        if target_length > self.max_word_len * self.max_words {
+            return invalid(format!(
+                "target length {} cannot be reached with {} words of at most {} letters",
+                target_length, self.max_words, self.max_word_len
+            ));
+        }
+        if self.render_rules.is_empty() {
+            return invalid(String::from("render rules need at least one separator, casing and trailing text"));
+        }
+        if self.resume && self.checkpoint.is_none() {
+            return invalid(String::from("resuming needs a checkpoint file"));
+        }
+        if self.checkpoint_interval.is_zero() {
+            return invalid(String::from("checkpoint interval must be greater than 0"));
+        }
+        if self.progress.as_ref().is_some_and(|(interval, _)| interval.is_zero()) {
+            return invalid(String::from("progress interval must be greater than 0"));
+        }
+        let mut passwords: Vec<Password> = vec![];
+        for (spec, template) in &self.targets {
+            let mut template = template.as_ref().unwrap_or(&self.template).clone();
+            if let Some(salt) = &spec.salt {
+                template.suffix.push_str(salt);
+            }
+            if template.iterations == 0 {
+                return invalid(format!("hash iterations for target '{}' must be greater than 0", spec.digest));
+            }
+            let mut password = Password::parse(&spec.digest, self.algorithm).map_err(SolverError::InvalidConfig)?.with_template(template);
+            if let Some(label) = &spec.label {
+                password = password.with_label(label);
+            }
+            match passwords.iter_mut().find(|existing| existing.is_same_target(&password)) {
+                Some(existing) => existing.merge(password),
+                None => passwords.push(password),
+            }
+        }
+        if passwords.len() < self.targets.len() {
+            info!("Merged {} duplicate target digests", self.targets.len() - passwords.len());
+        }
+
+        Ok(Solver {
+            dictionaries: self.dictionaries,
+            phrase,
+            min_word_len: self.min_word_len,
+            max_word_len: self.max_word_len,
+            max_words: self.max_words,
+            max_workers: self.max_workers,
+            hash_workers: self.hash_workers,
+            passwords,
+            render_rules: self.render_rules,
+            normalizer: self.normalizer,
+            clean_rules: self.clean_rules,
+            cancel: self.cancel,
+            checkpoint: self.checkpoint,
+            checkpoint_interval: self.checkpoint_interval,
+            resume: self.resume,
+            potfile: self.potfile,
+            progress: self.progress,
+            events: self.events,
+        })
+    }
+}
+
+pub struct Solver {
+    dictionaries: Vec<DictionarySource>,
+    phrase: Phrase,
+    min_word_len: usize,
+    max_word_len: usize,
+    max_words: usize,
+    max_workers: usize,
+    hash_workers: usize,
+    passwords: Vec<Password>,
+    render_rules: RenderRules,
+    normalizer: Normalizer,
+    clean_rules: CleanRules,
+    cancel: CancellationToken,
+    checkpoint: Option<PathBuf>,
+    checkpoint_interval: Duration,
+    resume: bool,
+    potfile: Option<PathBuf>,
+    progress: Option<(Duration, ProgressReport)>,
+    events: Option<EventSink>,
+}
+
+/// Word length patterns a run searched before it stopped.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Coverage {
+    pub patterns: usize,
+    pub searched: usize,
+    pub interrupted: usize,
+    pub skipped: usize,
+}
+
+impl Coverage {
+    pub fn is_exhaustive(&self) -> bool {
+        self.searched == self.patterns
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "searched {} of {} word length patterns ({} interrupted, {} skipped)",
+            self.searched, self.patterns, self.interrupted, self.skipped
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Solution {
+    pub passwords: Vec<Password>,
+    pub elapsed: Duration,
+    pub coverage: Coverage,
+    /// Candidate phrases compared with the targets.
+    pub candidates: u64,
+    /// Whether the run was stopped through the solver cancellation token.
+    pub interrupted: bool,
+}
+
+impl Solution {
+    pub fn found(&self) -> impl Iterator<Item = &Password> {
+        self.passwords.iter().filter(|password| password.is_found())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.passwords.iter().all(Password::is_found)
+    }
+}
+
+impl Solver {
+    pub fn builder() -> SolverBuilder {
+        SolverBuilder::new()
+    }
+
+    pub fn phrase(&self) -> &Phrase {
+        &self.phrase
+    }
+
+    pub fn solve(&self) -> Result<Solution, SolverError> {
+        if self.passwords.is_empty() {
+            return Err(SolverError::InvalidConfig(String::from("no target digests given")));
+        }
+        let started = Instant::now();
+        let dictionary = self.load_dictionary()?;
+        let patterns = self.patterns();
+        let fingerprint = self.fingerprint(&dictionary);
+        let mut passwords = self.passwords.clone();
+        let checkpoint = match &self.checkpoint {
+            Some(path) if self.resume => Some(self.load_checkpoint(path, &fingerprint)?),
+            _ => None,
+        };
+        if let Some(checkpoint) = &checkpoint {
+            checkpoint.restore(&mut passwords);
+            info!("Resuming with {} patterns completed and {} passwords found", checkpoint.completed.len(), checkpoint.found.len());
+        }
+        let potfile = match &self.potfile {
+            Some(path) => Some(Arc::new(Potfile::load(path).map_err(|e| SolverError::Potfile(path.clone(), e))?)),
+            None => None,
+        };
+        if let Some(potfile) = &potfile {
+            let restored = potfile.restore(&mut passwords);
+            info!("{} targets already cracked in the potfile", restored);
+        }
+        if let Some(events) = &self.events {
+            for (target, password) in passwords.iter().enumerate() {
+                if let Some(found) = Found::new(target, password) {
+                    events(&Event::Found(Found { restored: true, ..found }));
+                }
+            }
+        }
+        let progress = Arc::new(Progress::new(patterns.clone(), checkpoint.as_ref()));
+        let mut targets = Targets::new(passwords);
+        if let Some(events) = &self.events {
+            targets = targets.with_events(events.clone());
+        }
+        if let Some(potfile) = potfile {
+            targets = targets.with_potfile(potfile);
+        }
+        let targets = Arc::new(targets);
+        let ranks = dictionary.word_ranks();
+        let (comb_rx, search) = self.find_combinations(Arc::new(dictionary), patterns, Some(progress.clone()));
+        let comb_rx = Arc::new(Mutex::new(comb_rx));
+
+        let workers = (0..self.hash_workers).map(|_| {
+            let mut permutations_finder = PermutationsFinder::new(targets.clone(), comb_rx.clone(), self.render_rules.clone())
+                .with_cancellation(search.cancel.clone())
+                .with_progress(progress.clone())
+                .with_ranks(ranks.clone());
+            thread::spawn(move || permutations_finder.run())
+        }).collect::<Vec<_>>();
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let checkpointer = self.checkpoint.clone().map(|path| {
+            let (progress, targets, fingerprint) = (progress.clone(), targets.clone(), fingerprint.clone());
+            let interval = self.checkpoint_interval;
+            thread::spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+                    if let Err(e) = progress.checkpoint(&fingerprint, &targets.passwords()).save(&path) {
+                        warn!("Cannot write checkpoint {}: {}", path.display(), e);
+                    }
+                }
+            })
+        });
+        let (report_tx, report_rx) = mpsc::channel::<()>();
+        let reporter = self.progress.clone().map(|(interval, report)| {
+            let (meter, targets, events) = (search.meter.clone(), targets.clone(), self.events.clone());
+            thread::spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = report_rx.recv_timeout(interval) {
+                    let snapshot = meter.snapshot(Some(&targets));
+                    report(&snapshot);
+                    if let Some(events) = &events {
+                        events(&Event::Stats(snapshot));
+                    }
+                }
+                report(&meter.snapshot(Some(&targets)));
+            })
+        });
+        for worker in workers {
+            let _ = worker.join();
+        }
+        drop(comb_rx);
+        let coverage = search.finish();
+        drop(stop_tx);
+        if let Some(checkpointer) = checkpointer {
+            let _ = checkpointer.join();
+        }
+        drop(report_tx);
+        if let Some(reporter) = reporter {
+            let _ = reporter.join();
+        }
+        if let Some(events) = &self.events {
+            events(&Event::Stats(search.meter.snapshot(Some(&targets))));
+        }
+        let passwords = targets.passwords();
+        if let Some(path) = &self.checkpoint {
+            progress.checkpoint(&fingerprint, &passwords).save(path)
+                .map_err(|e| SolverError::Checkpoint(path.clone(), e.to_string()))?;
+        }
+        info!("Found passwords: {:?}", passwords.iter().filter_map(Password::phrase).collect::<Vec<&str>>());
+        info!("Search {}, {} candidates tested", coverage, targets.tested());
+        Ok(Solution {
+            passwords,
+            elapsed: started.elapsed(),
+            coverage,
+            candidates: targets.tested(),
+            interrupted: self.cancel.is_cancelled(),
+        })
+    }
+
+    /// Sizes up the search space of `solve` without running it. With `calibration`, the
+    /// permutations finder hashes a sample phrase for that long to estimate the run time,
+    /// assuming the hash workers scale linearly.
+    pub fn estimate(&self, calibration: Option<Duration>) -> Result<Estimate, SolverError> {
+        let dictionary = self.load_dictionary()?;
+        let words = (0..=self.max_word_len)
+            .map(|len| dictionary.classes(len).iter().map(|class| class.len()).sum::<usize>() as f64)
+            .collect::<Vec<f64>>();
+        let patterns = self.patterns().into_iter()
+            .map(|pattern| PatternEstimate::new(pattern, &words, self.render_rules.len()))
+            .collect::<Vec<PatternEstimate>>();
+        let rate = match calibration {
+            Some(duration) => self.calibrate(&dictionary, &patterns, duration)?,
+            None => None,
+        };
+        Ok(Estimate { patterns, rate })
+    }
+
+    /// Candidates per second of all hash workers, measured on a sample phrase of the longest pattern.
+    fn calibrate(&self, dictionary: &Dictionary, patterns: &[PatternEstimate], duration: Duration) -> Result<Option<f64>, SolverError> {
+        if self.passwords.is_empty() {
+            return Err(SolverError::InvalidConfig(String::from("calibration needs target digests")));
+        }
+        let sample = patterns.iter()
+            .sorted_by_key(|estimate| std::cmp::Reverse(estimate.pattern.len()))
+            .find_map(|estimate| {
+                estimate.pattern.iter()
+                    .map(|word_len| dictionary.classes(*word_len).first().map(|class| class[0].clone()))
+                    .collect::<Option<Vec<String>>>()
+            });
+        let Some(words) = sample else {
+            return Ok(None);
+        };
+        info!("Calibrating with {:?} for {:?}", words, duration);
+        let (_, rx) = mpsc::channel();
+        let targets = Arc::new(Targets::new(self.passwords.clone()));
+        let rate = PermutationsFinder::new(targets, Arc::new(Mutex::new(rx)), self.render_rules.clone()).calibrate(&words, duration);
+        Ok(Some(rate * self.hash_workers as f64))
+    }
+
+    /// Streams every distinct multi-word anagram of the phrase to `emit`, or every distinct
+    /// word order of them with `permutations`, stopping after `limit` anagrams.
+    /// Returns the number of anagrams emitted.
+    pub fn anagrams<F>(&self, permutations: bool, limit: Option<usize>, mut emit: F) -> Result<usize, SolverError>
+    where
+        F: FnMut(&[String], &[&[String]]) -> io::Result<()>,
+    {
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut seen: HashSet<Vec<String>> = HashSet::new();
+        let mut emitted = 0;
+        if limit == 0 {
+            return Ok(emitted);
+        }
+        let dictionary = Arc::new(self.load_dictionary()?);
+        let ranks = dictionary.word_ranks();
+        let (comb_rx, _search) = self.find_combinations(dictionary.clone(), self.patterns(), None);
+
+        for mut combination in comb_rx.iter().flat_map(|combination| expand_ranked(&combination, &ranks)) {
+            combination.sort();
+            if !seen.insert(combination.clone()) {
+                continue;
+            }
+            let anagrams = if permutations {
+                combination.iter().cloned().permutations(combination.len()).unique().collect::<Vec<Vec<String>>>()
+            } else {
+                vec![combination]
+            };
+            for anagram in anagrams {
+                let tags = anagram.iter().map(|word| dictionary.tags(word)).collect::<Vec<&[String]>>();
+                emit(&anagram, &tags).map_err(SolverError::Output)?;
+                emitted += 1;
+                if emitted == limit {
+                    return Ok(emitted);
+                }
+            }
+        }
+        Ok(emitted)
+    }
+
+    fn load_dictionary(&self) -> Result<Dictionary, SolverError> {
+        info!("Phrase '{}' has {} letters", self.phrase.text(), self.phrase.len());
+        let allowed_chars = self.phrase.letters().to_vec();
+        let dictionary = Dictionary::new(&self.dictionaries, self.min_word_len, self.max_word_len, &allowed_chars, &self.normalizer, &self.clean_rules)
+            .map_err(|(path, e)| SolverError::Dictionary(path, e))?;
+        info!("Dictionary loaded successfully, {} search tiers", dictionary.tiers());
+        Ok(dictionary)
+    }
+
+    fn patterns(&self) -> Vec<Vec<usize>> {
+        CombinationGenerator::new(self.phrase.len(), self.min_word_len, self.max_word_len, self.max_words).collect()
+    }
+
+    /// Identifies everything a checkpoint depends on, positions are only meaningful for
+    /// the same phrase, dictionary contents, targets and render rules.
+    fn fingerprint(&self, dictionary: &Dictionary) -> String {
+        let classes = (self.min_word_len..=self.max_word_len).map(|len| dictionary.classes(len).len()).collect::<Vec<usize>>();
+        let targets = self.passwords.iter().map(|password| (password.digest(), password.template())).collect::<Vec<_>>();
+        let description = format!(
+            "{:?}",
+            (self.phrase.letters(), &self.dictionaries, classes, self.min_word_len, self.max_word_len, self.max_words, targets, &self.render_rules, self.normalizer, &self.clean_rules)
+        );
+        hex::encode(HashAlgorithm::Sha256.hasher().digest(description.as_bytes()))
+    }
+
+    fn load_checkpoint(&self, path: &Path, fingerprint: &str) -> Result<Checkpoint, SolverError> {
+        let checkpoint = Checkpoint::load(path).map_err(|e| SolverError::Checkpoint(path.to_path_buf(), e.to_string()))?;
+        if checkpoint.fingerprint != fingerprint {
+            return Err(SolverError::Checkpoint(path.to_path_buf(), String::from("it was written for a different phrase, dictionary, targets or rules")));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Queues a combination finder for every word length pattern on a pool of `max_workers`
+    /// threads, once per search tier: a tier starts once the previous one is searched, so
+    /// combinations of the best ranked words come first. The returned receiver is bounded,
+    /// so finders wait while hashing falls behind, it disconnects once every pattern is
+    /// searched and dropping it stops the finders.
+    /// With `progress`, finished patterns are skipped and interrupted ones resumed.
+    fn find_combinations(
+        &self,
+        dictionary: Arc<Dictionary>,
+        patterns: Vec<Vec<usize>>,
+        progress: Option<Arc<Progress>>,
+    ) -> (Receiver<Combination>, Search) {
+        let classes = (0..=self.max_word_len).map(|len| dictionary.classes(len).len() as f64).collect::<Vec<f64>>();
+        let sizes = patterns.iter().map(|pattern| pattern.iter().map(|word_len| classes[*word_len]).product()).collect();
+        let tiers = dictionary.tiers();
+        let (comb_tx, comb_rx) = mpsc::sync_channel(self.max_workers * COMBINATIONS_PER_WORKER);
+        let search = Search {
+            pool: ThreadPool::new(self.max_workers),
+            cancel: self.cancel.child(),
+            states: Arc::new(Mutex::new(vec![PatternState::Queued; patterns.len()])),
+            meter: Arc::new(Meter::new(sizes)),
+            dispatcher: Mutex::new(None),
+        };
+        let task = Arc::new(PatternTask {
+            dictionary,
+            tiers,
+            comb_tx,
+            comparator: self.phrase.letters().to_vec(),
+            normalizer: self.normalizer,
+            cancel: search.cancel.clone(),
+            states: search.states.clone(),
+            progress,
+            meter: search.meter.clone(),
+            events: self.events.clone(),
+        });
+        let (pool, cancel) = (search.pool.clone(), search.cancel.clone());
+        let dispatcher = thread::spawn(move || {
+            for rank in 0..tiers {
+                if rank > 0 {
+                    pool.join();
+                    if cancel.is_cancelled() {
+                        break;
+                    }
+                    info!("Searching tier {} of {}", rank + 1, tiers);
+                }
+                for (index, pattern) in patterns.iter().enumerate() {
+                    let (task, pattern) = (task.clone(), pattern.clone());
+                    pool.execute(move || task.run(index, rank, pattern));
+                }
+            }
+        });
+        *search.dispatcher.lock().unwrap() = Some(dispatcher);
+
+        (comb_rx, search)
+    }
+}
+
+/// How far the finders of a word length pattern got over all search tiers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PatternState {
+    Queued,
+    /// Searched in some tiers, not in all of them yet.
+    Started,
+    Searched,
+    Interrupted,
+}
+
+/// Everything the combination finder of one pattern and tier needs, shared by all of them.
+struct PatternTask {
+    dictionary: Arc<Dictionary>,
+    tiers: usize,
+    comb_tx: SyncSender<Combination>,
+    comparator: Vec<char>,
+    normalizer: Normalizer,
+    cancel: CancellationToken,
+    states: Arc<Mutex<Vec<PatternState>>>,
+    progress: Option<Arc<Progress>>,
+    meter: Arc<Meter>,
+    events: Option<EventSink>,
+}
+
+impl PatternTask {
+    fn run(&self, index: usize, rank: usize, pattern: Vec<usize>) {
+        let last = rank + 1 == self.tiers;
+        if self.progress.as_ref().is_some_and(|progress| progress.is_done(index)) {
+            self.finish(index, last);
+            return;
+        }
+        if self.cancel.is_cancelled() {
+            return;
+        }
+        let classes = pattern.iter().map(|word_len| self.dictionary.ranked_classes(*word_len, rank)).collect::<Vec<Vec<AnagramClass>>>();
+        if let Some((word_len, _)) = pattern.iter().zip(&classes).find(|(_, list)| list.is_empty()) {
+            info!("Selected word length {} not found! Skipping {:?}...", word_len, pattern);
+            self.emit(Event::PatternFinished { pattern, rank, complete: true });
+            self.finish(index, last);
+            return;
+        }
+        info!("Combination {:?}", pattern);
+        self.emit(Event::PatternStarted { pattern: pattern.clone(), rank });
+        let mut finder = CombinationFinder::new(classes, self.comb_tx.clone(), self.comparator.clone())
+            .with_normalizer(self.normalizer)
+            .with_cancellation(self.cancel.clone())
+            .with_pattern(index)
+            .with_rank(rank, self.tiers)
+            .with_ranks(self.dictionary.word_ranks())
+            .with_meter(self.meter.clone());
+        if let Some(progress) = &self.progress {
+            if let Some(position) = progress.position(index) {
+                info!("Resuming {:?} from {:?}", pattern, position);
+                finder = finder.resume_from(position);
+            }
+            finder = finder.with_progress(progress.clone());
+        }
+        let finished = finder.run();
+        self.emit(Event::PatternFinished { pattern, rank, complete: finished });
+        if finished {
+            self.finish(index, last);
+        } else {
+            self.states.lock().unwrap()[index] = PatternState::Interrupted;
+        }
+    }
+
+    /// Records a tier of the pattern at `index` as searched, the pattern once `last` tier is.
+    fn finish(&self, index: usize, last: bool) {
+        let mut states = self.states.lock().unwrap();
+        if states[index] == PatternState::Searched {
+            return;
+        }
+        if !last {
+            states[index] = PatternState::Started;
+            return;
+        }
+        states[index] = PatternState::Searched;
+        if let Some(progress) = &self.progress {
+            progress.searched(index);
+        }
+        self.meter.finish(index);
+    }
+
+    fn emit(&self, event: Event) {
+        if let Some(events) = &self.events {
+            events(&event);
+        }
+    }
+}
+
+/// Combination finders queued for one run, dropping it cancels the ones still queued or running.
+struct Search {
+    pool: ThreadPool,
+    cancel: CancellationToken,
+    states: Arc<Mutex<Vec<PatternState>>>,
+    meter: Arc<Meter>,
+    dispatcher: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+impl Search {
+    /// Cancels the finders still queued or running and waits for them to stop.
+    fn finish(&self) -> Coverage {
+        self.cancel.cancel();
+        if let Some(dispatcher) = self.dispatcher.lock().unwrap().take() {
+            let _ = dispatcher.join();
+        }
+        self.pool.join();
+        let states = self.states.lock().unwrap();
+        let count = |state: PatternState| states.iter().filter(|candidate| **candidate == state).count();
+        Coverage {
+            patterns: states.len(),
+            searched: count(PatternState::Searched),
+            interrupted: count(PatternState::Interrupted) + count(PatternState::Started),
+            skipped: count(PatternState::Queued),
+        }
+    }
+}
+
+impl Drop for Search {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn solve_finds_all_passwords() {
+        let solver = Solver::builder()
+            .dictionary("testlist")
+            .phrase("Sitt thesis!")
+            .max_workers(2)
+            .hash_workers(3)
+            .targets(["8c6d115258631625b625486f81b09532", "d1e744eced74494c7a5292a93252eb6c"])
+            .build()
+            .unwrap();
+
+        let solution = solver.solve().unwrap();
+
+        assert!(solution.is_complete());
+        assert_eq!(solution.passwords[0].phrase(), Some("this is test"));
+        assert_eq!(solution.passwords[1].phrase(), Some("sit the sits"));
+    }
+
+    #[test]
+    fn solve_finds_passwords_of_mixed_algorithms() {
+        let solver = Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .max_workers(2)
+            .target(&hex::encode(HashAlgorithm::Sha1.hasher().digest(b"this is test")))
+            .target(&hex::encode(HashAlgorithm::Blake3.hasher().digest(b"sit the sits")))
+            .build()
+            .unwrap();
+
+        let solution = solver.solve().unwrap();
+
+        assert_eq!(solution.passwords[0].phrase(), Some("this is test"));
+        assert_eq!(solution.passwords[0].algorithm(), Some(HashAlgorithm::Sha1));
+        assert_eq!(solution.passwords[1].phrase(), Some("sit the sits"));
+        assert_eq!(solution.passwords[1].algorithm(), Some(HashAlgorithm::Blake3));
+    }
+
+    #[test]
+    fn solve_finds_salted_and_hmac_passwords() {
+        let salted = HashTemplate { suffix: String::from(":pepper"), ..HashTemplate::default() };
+        let keyed = HashTemplate { hmac_key: Some(b"secret".to_vec()), ..HashTemplate::default() };
+        let solver = Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .max_workers(2)
+            .template(salted.clone())
+            .target(&hex::encode(salted.apply(HashAlgorithm::Md5, "this is test")))
+            .target_with_template(&hex::encode(keyed.apply(HashAlgorithm::Sha256, "sit the sits")), keyed)
+            .build()
+            .unwrap();
+
+        let solution = solver.solve().unwrap();
+
+        assert_eq!(solution.passwords[0].phrase(), Some("this is test"));
+        assert_eq!(solution.passwords[1].phrase(), Some("sit the sits"));
+    }
+
+    #[test]
+    fn solve_returns_once_search_space_is_exhausted() {
+        let solver = Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .max_workers(1)
+            .target(&hex::encode(md5::compute(b"not an anagram").0))
+            .build()
+            .unwrap();
+
+        let solution = solver.solve().unwrap();
+
+        assert!(!solution.is_complete());
+        assert_eq!(solution.found().count(), 0);
+        assert!(solution.coverage.is_exhaustive());
+        assert!(solution.coverage.patterns > 0);
+        assert!(solution.candidates > 0);
+        assert!(!solution.interrupted);
+    }
+
+    #[test]
+    fn solve_stops_immediately_when_cancelled() {
+        let cancel = CancellationToken::new();
+        let solver = Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .max_workers(1)
+            .target(&hex::encode(md5::compute(b"this is test").0))
+            .cancellation(cancel.clone())
+            .build()
+            .unwrap();
+
+        cancel.cancel();
+        let solution = solver.solve().unwrap();
+
+        assert!(solution.interrupted);
+        assert_eq!(solution.candidates, 0);
+        assert!(!solution.is_complete());
+        assert!(!solution.coverage.is_exhaustive());
+        assert_eq!(solution.coverage.searched, 0);
+    }
+
+    fn checkpoint_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("anagram-solver-{}-{}.json", name, std::process::id()))
+    }
+
+    fn checkpointed(path: &Path, digests: &[&str]) -> SolverBuilder {
+        Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .max_workers(2)
+            .targets(digests)
+            .checkpoint(path)
+    }
+
+    #[test]
+    fn resume_continues_interrupted_run() {
+        let path = checkpoint_path("interrupted");
+        let digests = ["8c6d115258631625b625486f81b09532", "d1e744eced74494c7a5292a93252eb6c"];
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let interrupted = checkpointed(&path, &digests).cancellation(cancel).build().unwrap().solve().unwrap();
+
+        let resumed = checkpointed(&path, &digests).resume(true).build().unwrap().solve();
+        let checkpoint = Checkpoint::load(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(interrupted.found().count(), 0);
+        assert!(resumed.unwrap().is_complete());
+        assert_eq!(checkpoint.unwrap().found.len(), 2);
+    }
+
+    #[test]
+    fn resume_skips_completed_patterns_and_keeps_found_passwords() {
+        let path = checkpoint_path("completed");
+        let digests = ["8c6d115258631625b625486f81b09532", "00000000000000000000000000000000"];
+        let first = checkpointed(&path, &digests).build().unwrap().solve().unwrap();
+
+        let resumed = checkpointed(&path, &digests).resume(true).build().unwrap().solve();
+        let _ = std::fs::remove_file(&path);
+        let resumed = resumed.unwrap();
+
+        assert!(first.coverage.is_exhaustive());
+        assert_eq!(resumed.passwords[0].phrase(), Some("this is test"));
+        assert!(!resumed.passwords[1].is_found());
+        assert_eq!(resumed.coverage, first.coverage);
+    }
+
+    #[test]
+    fn resume_rejects_checkpoint_of_other_targets() {
+        let path = checkpoint_path("other");
+        let first = checkpointed(&path, &["8c6d115258631625b625486f81b09532"]).build().unwrap().solve();
+
+        let resumed = checkpointed(&path, &["d1e744eced74494c7a5292a93252eb6c"]).resume(true).build().unwrap().solve();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(first.is_ok());
+        assert!(matches!(resumed, Err(SolverError::Checkpoint(_, _))));
+    }
+
+    #[test]
+    fn solve_reports_final_progress() {
+        let snapshots = Arc::new(Mutex::new(vec![]));
+        let reported = snapshots.clone();
+        let solver = Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .max_workers(2)
+            .target(&hex::encode(md5::compute(b"not an anagram").0))
+            .progress(Duration::from_secs(60), move |snapshot| reported.lock().unwrap().push(snapshot.clone()))
+            .build()
+            .unwrap();
+
+        let solution = solver.solve().unwrap();
+        let snapshots = snapshots.lock().unwrap();
+
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].patterns_done, solution.coverage.patterns);
+        assert_eq!(snapshots[0].searched, 1.0);
+        assert_eq!(snapshots[0].candidates, solution.candidates);
+        assert_eq!(snapshots[0].targets, 1);
+    }
+
+    #[test]
+    fn estimate_covers_every_pattern() {
+        let solver = Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .target("8c6d115258631625b625486f81b09532")
+            .build()
+            .unwrap();
+
+        let estimate = solver.estimate(Some(Duration::from_millis(20))).unwrap();
+
+        assert_eq!(estimate.patterns.len(), solver.patterns().len());
+        assert!(estimate.permutations() >= estimate.tuples());
+        assert!(estimate.tuples() > 0.0);
+        assert!(estimate.duration().is_some());
+    }
+
+    #[test]
+    fn estimate_calibration_requires_targets() {
+        let solver = Solver::builder().dictionary("testlist").phrase("sitt thesis").build().unwrap();
+
+        assert!(solver.estimate(None).is_ok());
+        assert!(matches!(solver.estimate(Some(Duration::from_millis(20))), Err(SolverError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn solve_sends_events() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let sent = events.clone();
+        let solver = Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .max_workers(2)
+            .target(&hex::encode(md5::compute(b"not an anagram").0))
+            .target("8c6d115258631625b625486f81b09532")
+            .events(move |event| sent.lock().unwrap().push(event.clone()))
+            .build()
+            .unwrap();
+
+        let solution = solver.solve().unwrap();
+        let events = events.lock().unwrap();
+        let started = events.iter().filter(|event| matches!(event, Event::PatternStarted { .. })).count();
+        let finished = events.iter().filter(|event| matches!(event, Event::PatternFinished { complete: true, .. })).count();
+        let found = events.iter().filter_map(|event| match event {
+            Event::Found(found) => Some(found),
+            _ => None,
+        }).collect::<Vec<_>>();
+
+        assert_eq!(finished, solution.coverage.patterns);
+        assert!(started > 0 && started <= finished);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].target, found[0].phrase.as_str(), found[0].pattern.len()), (1, "this is test", 3));
+        assert!(matches!(events.last(), Some(Event::Stats(stats)) if stats.found == 1));
+    }
+
+    #[test]
+    fn solve_merges_duplicate_targets_and_applies_salts() {
+        let spec = |digest: &str, label: &str, salt: Option<&str>| TargetSpec {
+            digest: digest.to_string(),
+            label: Some(label.to_string()),
+            salt: salt.map(String::from),
+        };
+        let salted = HashTemplate { suffix: String::from("NaCl"), ..HashTemplate::default() };
+        let solver = Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .max_workers(2)
+            .target_specs([
+                spec("8c6d115258631625b625486f81b09532", "alice", None),
+                spec("8C6D115258631625B625486F81B09532", "bob", None),
+                spec(&hex::encode(salted.apply(HashAlgorithm::Md5, "sit the sits")), "carol", Some("NaCl")),
+            ])
+            .build()
+            .unwrap();
+
+        let solution = solver.solve().unwrap();
+
+        assert_eq!(solution.passwords.len(), 2);
+        assert_eq!(solution.passwords[0].labels(), ["alice", "bob"]);
+        assert_eq!(solution.passwords[0].phrase(), Some("this is test"));
+        assert_eq!(solution.passwords[1].phrase(), Some("sit the sits"));
+    }
+
+    #[test]
+    fn solve_appends_salts_to_template_suffix() {
+        let salted = HashTemplate { suffix: String::from(":NaCl"), ..HashTemplate::default() };
+        let solver = Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .max_workers(2)
+            .template(HashTemplate { suffix: String::from(":"), ..HashTemplate::default() })
+            .target_specs([TargetSpec {
+                digest: hex::encode(salted.apply(HashAlgorithm::Md5, "sit the sits")),
+                label: None,
+                salt: Some(String::from("NaCl")),
+            }])
+            .build()
+            .unwrap();
+
+        let solution = solver.solve().unwrap();
+
+        assert_eq!(solution.passwords[0].phrase(), Some("sit the sits"));
+        assert_eq!(solution.passwords[0].template(), &salted);
+    }
+
+    #[test]
+    fn solve_records_passwords_in_potfile_and_skips_them_later() {
+        let path = std::env::temp_dir().join(format!("anagram-solver-{}.pot", std::process::id()));
+        let solver = |digest: &str| Solver::builder()
+            .dictionary("testlist")
+            .phrase("sitt thesis")
+            .max_workers(2)
+            .target(digest)
+            .potfile(&path);
+        let events = Arc::new(Mutex::new(vec![]));
+        let sent = events.clone();
+
+        let first = solver("8c6d115258631625b625486f81b09532").build().unwrap().solve().unwrap();
+        let second = solver("md5:8C6D115258631625B625486F81B09532")
+            .events(move |event| sent.lock().unwrap().push(event.clone()))
+            .build()
+            .unwrap()
+            .solve();
+        let potfile = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+        let second = second.unwrap();
+
+        assert!(first.is_complete());
+        assert_eq!(potfile.unwrap(), "md5:8c6d115258631625b625486f81b09532:this is test\n");
+        assert_eq!(second.passwords[0].phrase(), Some("this is test"));
+        assert_eq!(second.candidates, 0);
+        assert!(matches!(events.lock().unwrap().first(), Some(Event::Found(found)) if found.restored && found.phrase == "this is test"));
+    }
+
+    #[test]
+    fn solve_requires_targets() {
+        let solver = Solver::builder().dictionary("testlist").phrase("sitt thesis").build().unwrap();
+
+        assert!(matches!(solver.solve(), Err(SolverError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn anagrams_emits_each_word_multiset_once() {
+        let solver = Solver::builder().dictionary("testlist").phrase("sitt thesis").max_workers(2).build().unwrap();
+        let mut anagrams = vec![];
+
+        let count = solver.anagrams(false, None, |words, _| {
+            anagrams.push(words.join(" "));
+            Ok(())
+        }).unwrap();
+        anagrams.sort();
+
+        assert_eq!(count, anagrams.len());
+        assert_eq!(anagrams, vec!["is test this", "sit sits the"]);
+    }
+
+    #[test]
+    fn anagrams_with_permutations_respects_limit() {
+        let solver = Solver::builder().dictionary("testlist").phrase("sitt thesis").max_workers(2).build().unwrap();
+        let mut anagrams: Vec<Vec<String>> = vec![];
+
+        let count = solver.anagrams(true, Some(4), |words, _| {
+            anagrams.push(words.to_vec());
+            Ok(())
+        }).unwrap();
+
+        assert_eq!(count, 4);
+        assert_eq!(anagrams.iter().unique().count(), 4);
+    }
+
+    fn ranked_dictionaries(name: &str) -> (PathBuf, Vec<DictionarySource>) {
+        let path = std::env::temp_dir().join(format!("anagram-solver-{}-{}.txt", name, std::process::id()));
+        std::fs::write(&path, "tiss\nthe\n").unwrap();
+        let sources = vec![
+            DictionarySource::new("testlist").with_tag("core"),
+            DictionarySource::new(&path).with_tag("jargon").with_rank(1),
+        ];
+        (path, sources)
+    }
+
+    #[test]
+    fn anagrams_of_core_words_come_first() {
+        let (path, sources) = ranked_dictionaries("ranked-anagrams");
+        let solver = Solver::builder().dictionaries(sources).phrase("sitt thesis").max_workers(2).build().unwrap();
+        let mut anagrams = vec![];
+
+        solver.anagrams(false, None, |words, tags| {
+            anagrams.push(words.iter().zip(tags).map(|(word, tags)| format!("{}[{}]", word, tags.join(","))).join(" "));
+            Ok(())
+        }).unwrap();
+        let _ = std::fs::remove_file(&path);
+        anagrams[..2].sort();
+
+        assert_eq!(anagrams, vec![
+            "is[core] test[core] this[core]",
+            "sit[core] sits[core] the[core,jargon]",
+            "sit[core] the[core,jargon] tiss[jargon]",
+        ]);
+    }
+
+    #[test]
+    fn solve_finds_passwords_using_lower_ranked_words() {
+        let (path, sources) = ranked_dictionaries("ranked-solve");
+        let solver = Solver::builder()
+            .dictionaries(sources)
+            .phrase("sitt thesis")
+            .max_workers(2)
+            .targets([format!("{:x}", md5::compute(b"tiss the sit")), String::from("8c6d115258631625b625486f81b09532")])
+            .build()
+            .unwrap();
+
+        let solution = solver.solve();
+        let _ = std::fs::remove_file(&path);
+        let solution = solution.unwrap();
+
+        assert!(solution.is_complete());
+        assert_eq!(solution.passwords[0].phrase(), Some("tiss the sit"));
+    }
+
+    #[test_case(false, vec![]; "accents kept")]
+    #[test_case(true, vec!["Bébé Café"]; "accents stripped")]
+    fn anagrams_keep_original_spelling(strip_accents: bool, expected: Vec<&str>) {
+        let path = std::env::temp_dir().join(format!("anagram-solver-accents-{}-{}", strip_accents, std::process::id()));
+        std::fs::write(&path, "Café\nBébé\n").unwrap();
+        let solver = Solver::builder().dictionary(&path).phrase("bebe face").strip_accents(strip_accents).build().unwrap();
+        let mut anagrams = vec![];
+
+        solver.anagrams(false, None, |words, _| {
+            anagrams.push(words.join(" "));
+            Ok(())
+        }).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(anagrams, expected);
+    }
+
+    #[test]
+    fn solve_finds_every_spelling_sharing_stripped_letters() {
+        let path = std::env::temp_dir().join(format!("anagram-solver-spellings-{}", std::process::id()));
+        std::fs::write(&path, "abé\nabe\nabe\n").unwrap();
+        let builder = || Solver::builder()
+            .dictionary(&path)
+            .phrase("abe")
+            .strip_accents(true)
+            .max_workers(2);
+        let mut anagrams = vec![];
+
+        let solution = builder()
+            .target(&hex::encode(md5::compute("abé").0))
+            .target(&hex::encode(md5::compute("abe").0))
+            .build()
+            .unwrap()
+            .solve();
+        let listed = builder().build().unwrap().anagrams(false, None, |words, _| {
+            anagrams.push(words.join(" "));
+            Ok(())
+        });
+        let _ = std::fs::remove_file(&path);
+        anagrams.sort();
+
+        assert!(solution.unwrap().is_complete());
+        assert_eq!(listed.unwrap(), 2);
+        assert_eq!(anagrams, vec!["abe", "abé"]);
+    }
+
+    #[test]
+    fn solve_reports_missing_dictionary() {
+        let solver = Solver::builder()
+            .dictionary("missing-wordlist")
+            .phrase("sitt thesis")
+            .target("8c6d115258631625b625486f81b09532")
+            .build()
+            .unwrap();
+
+        assert!(matches!(solver.solve(), Err(SolverError::Dictionary(_, _))));
+    }
+
+    #[test_case(Solver::builder().target("8c6d115258631625b625486f81b09532"); "missing phrase")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("xyz"); "malformed digest")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").min_word_len(0); "zero min word length")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").min_word_len(6).max_word_len(5); "min above max")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").max_words(0); "zero max words")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").max_workers(0); "zero workers")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").hash_workers(0); "zero hash workers")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").max_words(1).max_word_len(5); "unreachable target length")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").target_length(11); "inconsistent target length")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").template(HashTemplate { iterations: 0, ..HashTemplate::default() }); "zero hash iterations")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").render_rules(RenderRules { separators: vec![], ..RenderRules::default() }); "no separators")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").resume(true); "resume without checkpoint")]
+    #[test_case(Solver::builder().phrase("sitt thesis").target("8c6d115258631625b625486f81b09532").progress(Duration::ZERO, |_| ()); "zero progress interval")]
+    fn build_rejects_invalid_config(builder: SolverBuilder) {
+        assert!(matches!(builder.build(), Err(SolverError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use crate::hasher::HashAlgorithm;
+
+/// Layout of the lines of a target digest list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetFormat {
+    /// Digest alone, optionally prefixed with its algorithm.
+    Plain,
+    /// `user:digest`, the user labels the results.
+    UserHash,
+    /// Hashcat style `digest:salt`, the salt is appended to every candidate phrase after the suffix.
+    HashSalt,
+}
+
+impl TargetFormat {
+    pub const ALL: [TargetFormat; 3] = [TargetFormat::Plain, TargetFormat::UserHash, TargetFormat::HashSalt];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetFormat::Plain => "plain",
+            TargetFormat::UserHash => "user-hash",
+            TargetFormat::HashSalt => "hash-salt",
+        }
+    }
+}
+
+impl fmt::Display for TargetFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for TargetFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase().replace('_', "-");
+        TargetFormat::ALL.iter()
+            .find(|format| format.name() == name)
+            .copied()
+            .ok_or_else(|| format!("unknown target format '{}', expected one of: {}", s, TargetFormat::ALL.map(|f| f.name()).join(", ")))
+    }
+}
+
+/// Target read from a digest list, `digest` may still carry an algorithm prefix.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TargetSpec {
+    pub digest: String,
+    pub label: Option<String>,
+    pub salt: Option<String>,
+}
+
+impl TargetSpec {
+    /// Parses one line of a digest list, blank lines and `#` comments give `None`.
+    pub fn parse(line: &str, format: TargetFormat) -> Result<Option<Self>, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let spec = match format {
+            TargetFormat::Plain => TargetSpec { digest: line.to_string(), ..TargetSpec::default() },
+            TargetFormat::UserHash => match line.split_once(':') {
+                Some((user, digest)) if !user.is_empty() && !digest.is_empty() => TargetSpec {
+                    digest: digest.to_string(),
+                    label: Some(user.to_string()),
+                    salt: None,
+                },
+                _ => return Err(format!("expected user:hash, got '{}'", line)),
+            },
+            TargetFormat::HashSalt => {
+                let split = match line.split_once(':') {
+                    Some((name, rest)) if name.parse::<HashAlgorithm>().is_ok() => rest.split_once(':')
+                        .map(|(digest, salt)| (format!("{}:{}", name, digest), salt)),
+                    split => split.map(|(digest, salt)| (digest.to_string(), salt)),
+                };
+                match split {
+                    Some((digest, salt)) if !digest.is_empty() => TargetSpec { digest, label: None, salt: Some(salt.to_string()) },
+                    _ => return Err(format!("expected hash:salt, got '{}'", line)),
+                }
+            }
+        };
+        Ok(Some(spec))
+    }
+}
+
+/// Reads a digest list, errors name the offending line.
+pub fn read_targets<R: BufRead>(reader: R, format: TargetFormat) -> Result<Vec<TargetSpec>, String> {
+    let mut specs = vec![];
+    for (number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if let Some(spec) = TargetSpec::parse(&line, format).map_err(|e| format!("line {}: {}", number + 1, e))? {
+            specs.push(spec);
+        }
+    }
+    Ok(specs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn spec(digest: &str, label: Option<&str>, salt: Option<&str>) -> TargetSpec {
+        TargetSpec { digest: digest.to_string(), label: label.map(String::from), salt: salt.map(String::from) }
+    }
+
+    #[test_case("8c6d115258631625b625486f81b09532", TargetFormat::Plain, spec("8c6d115258631625b625486f81b09532", None, None); "plain")]
+    #[test_case("  md5:8c6d115258631625b625486f81b09532 ", TargetFormat::Plain, spec("md5:8c6d115258631625b625486f81b09532", None, None); "plain with algorithm")]
+    #[test_case("alice:8c6d115258631625b625486f81b09532", TargetFormat::UserHash, spec("8c6d115258631625b625486f81b09532", Some("alice"), None); "user hash")]
+    #[test_case("bob:sha1:a9993e364706816aba3e25717850c26c9cd0d89d", TargetFormat::UserHash, spec("sha1:a9993e364706816aba3e25717850c26c9cd0d89d", Some("bob"), None); "user hash with algorithm")]
+    #[test_case("8c6d115258631625b625486f81b09532:s:lt", TargetFormat::HashSalt, spec("8c6d115258631625b625486f81b09532", None, Some("s:lt")); "hash salt")]
+    #[test_case("md5:8c6d115258631625b625486f81b09532:salt", TargetFormat::HashSalt, spec("md5:8c6d115258631625b625486f81b09532", None, Some("salt")); "hash salt with algorithm")]
+    fn parse_returns_expected(line: &str, format: TargetFormat, expected: TargetSpec) {
+        assert_eq!(TargetSpec::parse(line, format), Ok(Some(expected)));
+    }
+
+    #[test_case("8c6d115258631625b625486f81b09532", TargetFormat::UserHash; "user hash without user")]
+    #[test_case(":8c6d115258631625b625486f81b09532", TargetFormat::UserHash; "user hash with empty user")]
+    #[test_case("8c6d115258631625b625486f81b09532", TargetFormat::HashSalt; "hash salt without salt")]
+    fn parse_rejects_malformed_line(line: &str, format: TargetFormat) {
+        assert!(TargetSpec::parse(line, format).is_err());
+    }
+
+    #[test]
+    fn read_targets_skips_comments_and_names_bad_lines() {
+        let list = "# users\nalice:8c6d115258631625b625486f81b09532\n\nbob:d1e744eced74494c7a5292a93252eb6c\n";
+
+        let specs = read_targets(list.as_bytes(), TargetFormat::UserHash).unwrap();
+
+        assert_eq!(specs.iter().map(|spec| spec.label.as_deref()).collect::<Vec<_>>(), vec![Some("alice"), Some("bob")]);
+        assert_eq!(
+            read_targets("alice:8c6d115258631625b625486f81b09532\nd1e744eced74494c7a5292a93252eb6c".as_bytes(), TargetFormat::UserHash).unwrap_err(),
+            "line 2: expected user:hash, got 'd1e744eced74494c7a5292a93252eb6c'"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use crate::events::{Event, EventSink, Found};
+use crate::hasher::HashAlgorithm;
+use crate::password::Password;
+use crate::potfile::Potfile;
+use crate::renderer::Rendering;
+
+/// Passwords shared by the hashing workers. Workers hash against their own snapshot of the
+/// targets still missing and only take the lock to record a match, so the hot path is a
+/// single atomic load telling whether the snapshot went stale.
+pub struct Targets {
+    passwords: RwLock<Vec<Password>>,
+    total: usize,
+    found: AtomicUsize,
+    tested: AtomicU64,
+    started: Instant,
+    events: Option<EventSink>,
+    potfile: Option<Arc<Potfile>>,
+}
+
+impl Targets {
+    pub fn new(passwords: Vec<Password>) -> Self {
+        let total = passwords.len();
+        let found = passwords.iter().filter(|password| password.is_found()).count();
+        Targets {
+            passwords: RwLock::new(passwords),
+            total,
+            found: AtomicUsize::new(found),
+            tested: AtomicU64::new(0),
+            started: Instant::now(),
+            events: None,
+            potfile: None,
+        }
+    }
+
+    /// Sends a found event to `events` for every password recorded.
+    pub fn with_events(mut self, events: EventSink) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Appends every password recorded to `potfile`.
+    pub fn with_potfile(mut self, potfile: Arc<Potfile>) -> Self {
+        self.potfile = Some(potfile);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Number of passwords found so far, it changes whenever a snapshot goes stale.
+    pub fn found(&self) -> usize {
+        self.found.load(Ordering::Acquire)
+    }
+
+    /// Number of candidate phrases compared with the targets so far.
+    pub fn tested(&self) -> u64 {
+        self.tested.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_tested(&self, candidates: u64) {
+        self.tested.fetch_add(candidates, Ordering::Relaxed);
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.found() == self.total
+    }
+
+    /// Passwords not found yet, paired with their position in the target list.
+    pub fn snapshot(&self) -> Vec<(usize, Password)> {
+        self.passwords.read().unwrap().iter()
+            .enumerate()
+            .filter(|(_, password)| !password.is_found())
+            .map(|(i, password)| (i, password.clone()))
+            .collect()
+    }
+
+    pub fn passwords(&self) -> Vec<Password> {
+        self.passwords.read().unwrap().clone()
+    }
+
+    /// Records a match for the password at `index`, returns `false` when another worker
+    /// found it first.
+    pub(crate) fn mark_found(&self, index: usize, phrase: &str, words: Vec<String>, algorithm: HashAlgorithm, rendering: Rendering) -> bool {
+        let mut passwords = self.passwords.write().unwrap();
+        if passwords[index].is_found() {
+            return false;
+        }
+        passwords[index].mark_found(phrase, words, algorithm, Some(rendering), Some(self.started.elapsed()));
+        self.found.fetch_add(1, Ordering::Release);
+        let password = passwords[index].clone();
+        drop(passwords);
+        if let Some(potfile) = &self.potfile {
+            if let Err(e) = potfile.record(&password) {
+                warn!("Cannot append to potfile: {}", e);
+            }
+        }
+        if let (Some(events), Some(found)) = (&self.events, Found::new(index, &password)) {
+            events(&Event::Found(found));
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    fn words(phrase: &str) -> Vec<String> {
+        phrase.split(' ').map(String::from).collect()
+    }
+
+    fn targets() -> Targets {
+        Targets::new(vec![
+            Password::new(HashAlgorithm::Md5, md5::compute(b"this is test").to_vec()),
+            Password::new(HashAlgorithm::Md5, md5::compute(b"sit the sits").to_vec()),
+        ])
+    }
+
+    #[test]
+    fn snapshot_skips_found_passwords() {
+        let targets = targets();
+
+        targets.mark_found(0, "this is test", words("this is test"), HashAlgorithm::Md5, Rendering::default());
+
+        assert_eq!(targets.snapshot().iter().map(|(i, _)| *i).collect::<Vec<usize>>(), vec![1]);
+        assert_eq!(targets.found(), 1);
+        assert!(!targets.is_complete());
+    }
+
+    #[test]
+    fn mark_found_keeps_first_match() {
+        let targets = targets();
+
+        assert!(targets.mark_found(1, "sit the sits", words("sit the sits"), HashAlgorithm::Md5, Rendering::default()));
+        assert!(!targets.mark_found(1, "sits the sit", words("sits the sit"), HashAlgorithm::Md5, Rendering::default()));
+
+        assert_eq!(targets.passwords()[1].phrase(), Some("sit the sits"));
+        assert_eq!(targets.found(), 1);
+    }
+
+    #[test]
+    fn mark_found_sends_found_event() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let sent = events.clone();
+        let targets = targets().with_events(Arc::new(move |event: &Event| sent.lock().unwrap().push(event.clone())));
+
+        targets.mark_found(1, "sit the sits", words("sit the sits"), HashAlgorithm::Md5, Rendering::default());
+
+        let events = events.lock().unwrap();
+        match &events[..] {
+            [Event::Found(found)] => {
+                assert_eq!((found.target, found.phrase.as_str()), (1, "sit the sits"));
+                assert_eq!(found.pattern, vec![3, 3, 4]);
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+    }
+}